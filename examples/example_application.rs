@@ -1,16 +1,19 @@
 use rae_app::*;
 
-use application::Application;
+use application::{
+    ApplicationBuilder, ApplicationHandle, ExitReason, FixedUpdateChannelId, JobHandle, SpawnError,
+    Timer, TimerAction,
+};
 use event::{
-    controller, keyboard, mouse, touch, ControlFlow, DeviceId, EventHandler, EventLoop,
-    EventLoopClosed, EventLoopProxy, EventLoopStartCause, ScrollDelta,
+    controller, keyboard, mouse, touch, ControlFlow, DeviceId, EventContext, EventHandler,
+    EventLoop, EventLoopStartCause, ExitResponse, Layer, LayerCallback, LayerResponse, ScrollDelta,
 };
-use window::{PhysicalPosition, PhysicalSize, Size, Window, WindowBuilder, WindowId};
+use window::{PhysicalPosition, PhysicalSize, Size, Theme, Window, WindowBuilder, WindowId};
 
 #[derive(Debug)]
 enum ApplicationError {
     WindowCreationError(window::OsError),
-    CustomEventSendingError(EventLoopClosed<CustomEvent>),
+    TaskSpawnError(SpawnError),
 }
 
 impl std::fmt::Display for ApplicationError {
@@ -19,9 +22,7 @@ impl std::fmt::Display for ApplicationError {
             ApplicationError::WindowCreationError(e) => {
                 write!(f, "Failed to create window ({})", e)
             }
-            ApplicationError::CustomEventSendingError(e) => {
-                write!(f, "Failed to send a custom event ({})", e)
-            }
+            ApplicationError::TaskSpawnError(e) => write!(f, "Failed to spawn task ({})", e),
         }
     }
 }
@@ -30,7 +31,7 @@ impl std::error::Error for ApplicationError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             ApplicationError::WindowCreationError(e) => Some(e),
-            ApplicationError::CustomEventSendingError(e) => Some(e),
+            ApplicationError::TaskSpawnError(e) => Some(e),
         }
     }
 }
@@ -41,24 +42,28 @@ impl From<window::OsError> for ApplicationError {
     }
 }
 
-impl From<EventLoopClosed<CustomEvent>> for ApplicationError {
-    fn from(e: EventLoopClosed<CustomEvent>) -> Self {
-        ApplicationError::CustomEventSendingError(e)
+impl From<SpawnError> for ApplicationError {
+    fn from(e: SpawnError) -> Self {
+        ApplicationError::TaskSpawnError(e)
     }
 }
 
+const SLOW_CHANNEL: FixedUpdateChannelId = FixedUpdateChannelId("slow");
+
 #[derive(Debug, Clone, Copy)]
 enum CustomEvent {
     SomeTimePassed,
     LongTimePassed,
+    GreetingCompleted,
+    PrimesCounted(usize),
 }
 
 #[derive(Debug)]
 struct ApplicationImpl {
-    _window: Window,
-    event_loop_proxy: EventLoopProxy<CustomEvent>,
+    window: Window,
+    extra_windows: Vec<Window>,
+    application: ApplicationHandle<CustomEvent>,
     processed_fixed_frames: u64,
-    processed_variable_frames: u64,
     processed_cursor_moved_events: u64,
     processed_device_cursor_moved_events: u64,
     processed_device_axis_moved_events: u64,
@@ -67,11 +72,14 @@ struct ApplicationImpl {
     processed_redraw_events_cleared_events: u64,
 }
 
-impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
+impl EventHandler for ApplicationImpl {
     type Error = ApplicationError;
     type CustomEvent = CustomEvent;
 
-    fn new(event_loop: &EventLoop<Self::CustomEvent>) -> Result<Self, Self::Error> {
+    fn new(
+        event_loop: &EventLoop<Self::CustomEvent>,
+        application: &ApplicationHandle<Self::CustomEvent>,
+    ) -> Result<Self, Self::Error> {
         let window = WindowBuilder::new()
             .with_title("Example application")
             .with_inner_size(Size::Physical(PhysicalSize {
@@ -79,11 +87,26 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
                 height: 600,
             }))
             .build(event_loop)?;
+        application.schedule(Timer::repeating(
+            std::time::Duration::from_secs(1),
+            TimerAction::event(CustomEvent::SomeTimePassed),
+        ));
+        application.schedule(Timer::repeating(
+            std::time::Duration::from_secs(3),
+            TimerAction::event(CustomEvent::LongTimePassed),
+        ));
+        let handle = application.clone();
+        application.spawn(async move {
+            println!("Hello...");
+            handle.sleep(std::time::Duration::from_secs(2)).await;
+            println!("...world!");
+            CustomEvent::GreetingCompleted
+        })?;
         Ok(Self {
-            _window: window,
-            event_loop_proxy: event_loop.create_proxy(),
+            window,
+            extra_windows: Vec::new(),
+            application: application.clone(),
             processed_fixed_frames: 0,
-            processed_variable_frames: 0,
             processed_cursor_moved_events: 0,
             processed_device_cursor_moved_events: 0,
             processed_device_axis_moved_events: 0,
@@ -93,55 +116,94 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         })
     }
 
-    fn on_fixed_update(&mut self, dt: std::time::Duration) -> Result<ControlFlow, Self::Error> {
-        if self.processed_fixed_frames % 30 == 0 {
-            println!("Processed 'fixed update' event, dt = {:?}", dt);
+    fn on_fixed_update(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        channel: FixedUpdateChannelId,
+        dt: std::time::Duration,
+    ) -> Result<ControlFlow, Self::Error> {
+        if channel == SLOW_CHANNEL {
+            println!(
+                "Processed 'fixed update' event, channel {}, dt = {:?}",
+                channel, dt
+            );
+            return Ok(ControlFlow::Continue);
         }
 
         if self.processed_fixed_frames % 30 == 0 {
-            self.event_loop_proxy
-                .send_event(CustomEvent::SomeTimePassed)?;
-        }
-
-        if self.processed_fixed_frames % 90 == 0 {
-            self.event_loop_proxy
-                .send_event(CustomEvent::LongTimePassed)?;
+            println!("Processed 'fixed update' event, dt = {:?}", dt);
         }
 
         self.processed_fixed_frames = self.processed_fixed_frames + 1;
         Ok(ControlFlow::Continue)
     }
 
-    fn on_variable_update(&mut self, dt: std::time::Duration) -> Result<ControlFlow, Self::Error> {
-        if self.processed_variable_frames % 30 == 0 {
-            println!("Processed 'variable update' event, dt = {:?}", dt);
+    fn on_variable_update(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        dt: std::time::Duration,
+        fixed_update_alpha: f64,
+    ) -> Result<ControlFlow, Self::Error> {
+        let frame_stats = self.application.frame_stats();
+        if frame_stats.total_frame_count() % 30 == 0 {
+            println!(
+                "Processed 'variable update' event, dt = {:?}, fixed update alpha = {:?}, \
+                real time = {:?}, fps = {:.1}",
+                dt,
+                fixed_update_alpha,
+                self.application.real_time(),
+                frame_stats.frames_per_second()
+            );
         }
-        self.processed_variable_frames = self.processed_variable_frames + 1;
         Ok(ControlFlow::Continue)
     }
 
-    fn on_close_requested(&mut self, wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_close_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'close requested' event, window {:?}.", wid);
+        if let Some(i) = self.extra_windows.iter().position(|w| w.id() == wid) {
+            self.extra_windows.remove(i);
+            return Ok(ControlFlow::Continue);
+        }
         Ok(ControlFlow::Exit)
     }
 
-    fn on_destroyed(&mut self, wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_destroyed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'destroyed' event, window {:?}.", wid);
+        if wid != self.window.id() {
+            return Ok(ControlFlow::Continue);
+        }
         Ok(ControlFlow::Exit)
     }
 
-    fn on_focus_gained(&mut self, wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_focus_gained(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'focus gained' event, window {:?}", wid);
         Ok(ControlFlow::Continue)
     }
 
-    fn on_focus_lost(&mut self, wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_focus_lost(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'focus lost' event, window {:?}", wid);
         Ok(ControlFlow::Continue)
     }
 
     fn on_received_character(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         c: char,
     ) -> Result<ControlFlow, Self::Error> {
@@ -154,6 +216,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_resized(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         size: PhysicalSize<u32>,
     ) -> Result<ControlFlow, Self::Error> {
@@ -166,6 +229,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_scale_factor_changed<'a>(
         &self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         scale_factor: f64,
         new_inner_size: &'a mut PhysicalSize<u32>,
@@ -179,6 +243,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         position: PhysicalPosition<i32>,
     ) -> Result<ControlFlow, Self::Error> {
@@ -191,6 +256,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_hovered_file_dropped(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         path: std::path::PathBuf,
     ) -> Result<ControlFlow, Self::Error> {
@@ -203,6 +269,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_hovered_file_entered(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         path: std::path::PathBuf,
     ) -> Result<ControlFlow, Self::Error> {
@@ -213,13 +280,18 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_hovered_file_left(&mut self, wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_hovered_file_left(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'hovered file left' event, window {:?}", wid);
         Ok(ControlFlow::Continue)
     }
 
     fn on_key_pressed(
         &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         scan_code: keyboard::ScanCode,
@@ -234,11 +306,72 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
                 wid, device_id, scan_code, key_code, is_repeat, is_synthetic
             );
         }
+        match key_code {
+            Some(keyboard::KeyCode::P) if !is_repeat => {
+                if self.application.is_paused() {
+                    self.application.resume();
+                } else {
+                    self.application.pause();
+                }
+                println!("Paused: {:?}", self.application.is_paused());
+            }
+            Some(keyboard::KeyCode::N) => self.application.step_fixed_updates(1),
+            Some(keyboard::KeyCode::W) if !is_repeat => {
+                if let Some(window_target) = context.window_target() {
+                    let window = WindowBuilder::new()
+                        .with_title("Extra window")
+                        .build(window_target)?;
+                    self.extra_windows.push(window);
+                }
+            }
+            Some(keyboard::KeyCode::Escape) => context.request_exit(),
+            Some(keyboard::KeyCode::J) if !is_repeat => {
+                self.application.submit_job(|context| {
+                    const LIMIT: usize = 200_000;
+                    let mut count = 0;
+                    for n in 2..LIMIT {
+                        if context.is_cancelled() {
+                            break;
+                        }
+                        if (2..).take_while(|d| d * d <= n).all(|d| n % d != 0) {
+                            count += 1;
+                        }
+                        if n % (LIMIT / 4) == 0 {
+                            context.report_progress(n as f32 / LIMIT as f32);
+                        }
+                    }
+                    CustomEvent::PrimesCounted(count)
+                });
+            }
+            Some(keyboard::KeyCode::F) if !is_repeat => {
+                let frequency = match self.application.variable_update_max_frequency() {
+                    Some(_) => None,
+                    None => Some(60),
+                };
+                self.application
+                    .set_variable_update_max_frequency(frequency)
+                    .expect("Invalid frequency");
+                println!("Variable update max frequency: {:?}", frequency);
+            }
+            Some(keyboard::KeyCode::Add) | Some(keyboard::KeyCode::Subtract) => {
+                let factor = if key_code == Some(keyboard::KeyCode::Add) {
+                    2.
+                } else {
+                    0.5
+                };
+                self.application
+                    .set_time_scale(self.application.time_scale() * factor)
+                    .expect("Invalid time scale");
+                println!("Time scale: {:?}", self.application.time_scale());
+            }
+            _ => (),
+        }
         Ok(ControlFlow::Continue)
     }
 
     fn on_key_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         scan_code: keyboard::ScanCode,
@@ -255,6 +388,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_cursor_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         position: PhysicalPosition<f64>,
@@ -271,6 +405,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_cursor_entered(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -283,6 +418,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_cursor_left(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -295,6 +431,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_modifiers_changed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         modifiers_state: keyboard::ModifiersState,
     ) -> Result<ControlFlow, Self::Error> {
@@ -307,6 +444,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_mouse_button_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         button: mouse::Button,
@@ -320,6 +458,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_mouse_button_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         button: mouse::Button,
@@ -333,6 +472,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_scroll(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         delta: ScrollDelta,
@@ -347,6 +487,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_axis_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         axis: controller::AxisId,
@@ -359,8 +500,37 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
+    fn on_theme_changed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+        theme: Theme,
+    ) -> Result<ControlFlow, Self::Error> {
+        println!(
+            "Processed 'theme changed' event, window {:?}, theme {:?}",
+            wid, theme
+        );
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_touchpad_pressure(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+        device_id: DeviceId,
+        pressure: f32,
+        stage: i64,
+    ) -> Result<ControlFlow, Self::Error> {
+        println!(
+            "Processed 'touchpad pressure' event, window {:?}, device {:?}, pressure {:?}, stage {:?}",
+            wid, device_id, pressure, stage
+        );
+        Ok(ControlFlow::Continue)
+    }
+
     fn on_touch(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         wid: WindowId,
         device_id: DeviceId,
         phase: touch::TouchPhase,
@@ -376,18 +546,27 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_device_added(&mut self, device_id: DeviceId) -> Result<ControlFlow, Self::Error> {
+    fn on_device_added(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        device_id: DeviceId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'device added' event, device {:?}", device_id);
         Ok(ControlFlow::Continue)
     }
 
-    fn on_device_removed(&mut self, device_id: DeviceId) -> Result<ControlFlow, Self::Error> {
+    fn on_device_removed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        device_id: DeviceId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'device removed' event, device {:?}", device_id);
         Ok(ControlFlow::Continue)
     }
 
     fn on_device_cursor_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         position_delta: PhysicalPosition<f64>,
     ) -> Result<ControlFlow, Self::Error> {
@@ -403,6 +582,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_scroll(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         scroll_delta: ScrollDelta,
     ) -> Result<ControlFlow, Self::Error> {
@@ -415,6 +595,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_axis_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         axis: controller::AxisId,
         value: f64,
@@ -431,6 +612,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_button_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         button: controller::ButtonId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -443,6 +625,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_button_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         button: controller::ButtonId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -455,6 +638,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_key_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         scan_code: keyboard::ScanCode,
         key_code: Option<keyboard::KeyCode>,
@@ -472,6 +656,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_key_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         scan_code: keyboard::ScanCode,
         key_code: Option<keyboard::KeyCode>,
@@ -485,6 +670,7 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
 
     fn on_device_text(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         device_id: DeviceId,
         codepoint: char,
     ) -> Result<ControlFlow, Self::Error> {
@@ -495,13 +681,53 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_custom_event(&mut self, event: Self::CustomEvent) -> Result<ControlFlow, Self::Error> {
+    fn on_custom_event(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        event: Self::CustomEvent,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'custom' event, {:?}", event);
+        if let CustomEvent::PrimesCounted(count) = event {
+            println!("Found {} primes", count);
+        }
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_job_progress(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        job: JobHandle,
+        progress: f32,
+    ) -> Result<ControlFlow, Self::Error> {
+        println!(
+            "Processed 'job progress' event, job {:?}, progress {:?}",
+            job, progress
+        );
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_job_completed(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        job: JobHandle,
+        event: Self::CustomEvent,
+    ) -> Result<ControlFlow, Self::Error> {
+        println!("Processed 'job completed' event, job {:?}", job);
+        self.on_custom_event(context, event)
+    }
+
+    fn on_job_failed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        job: JobHandle,
+    ) -> Result<ControlFlow, Self::Error> {
+        println!("Processed 'job failed' event, job {:?}", job);
         Ok(ControlFlow::Continue)
     }
 
     fn on_new_events(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         start_cause: EventLoopStartCause,
     ) -> Result<ControlFlow, Self::Error> {
         if self.processed_new_events_events % 100000 == 0 {
@@ -514,7 +740,10 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_main_events_cleared(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_main_events_cleared(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         if self.processed_main_events_cleared_events % 100000 == 0 {
             println!("Processed 'main events cleared' event");
         }
@@ -522,12 +751,19 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_redraw_requested(&mut self, wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_redraw_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'redraw requested' event, window id {:?}", wid);
         Ok(ControlFlow::Continue)
     }
 
-    fn on_redraw_events_cleared(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_redraw_events_cleared(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         if self.processed_redraw_events_cleared_events % 100000 == 0 {
             println!("Processed 'redraw events cleared' event");
         }
@@ -536,24 +772,94 @@ impl EventHandler<ApplicationError, CustomEvent> for ApplicationImpl {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_suspended(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_suspended(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'suspended' event");
         Ok(ControlFlow::Continue)
     }
 
-    fn on_resumed(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_resumed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'resumed' event");
         Ok(ControlFlow::Continue)
     }
 
-    fn on_event_loop_destroyed(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_event_loop_destroyed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         println!("Processed 'event loop destroyed' event");
         Ok(ControlFlow::Continue)
     }
+
+    fn on_exit_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        reason: ExitReason,
+    ) -> Result<ExitResponse, Self::Error> {
+        println!("Processed 'exit requested' event, reason {:?}", reason);
+        Ok(ExitResponse::Accept)
+    }
+
+    fn on_shutdown(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        reason: ExitReason,
+    ) -> Result<(), Self::Error> {
+        println!("Processed 'shutdown' event, reason {:?}", reason);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct FpsLayer {
+    frames: u32,
+    last_report_time: std::time::Duration,
+}
+
+impl Layer<ApplicationImpl> for FpsLayer {
+    fn on_callback(
+        &mut self,
+        context: &mut EventContext<CustomEvent>,
+        callback: &LayerCallback<CustomEvent>,
+    ) -> Result<LayerResponse, ApplicationError> {
+        if let LayerCallback::MainEventsCleared = callback {
+            self.frames += 1;
+            let real_time = context.real_time();
+            if real_time - self.last_report_time >= std::time::Duration::from_secs(5) {
+                let elapsed = (real_time - self.last_report_time).as_secs_f64();
+                println!("FPS: {:.1}", f64::from(self.frames) / elapsed);
+                self.frames = 0;
+                self.last_report_time = real_time;
+            }
+        }
+        Ok(LayerResponse::Pass)
+    }
 }
 
 fn main() {
     const FIXED_FRAMERATE: u64 = 30;
     const VARIABLE_FRAMERATE_CAP: u64 = 60;
-    Application::<ApplicationImpl, _, _>::new(FIXED_FRAMERATE, Some(VARIABLE_FRAMERATE_CAP)).run();
+    let mut application = ApplicationBuilder::new()
+        .with_fixed_update_frequency(FIXED_FRAMERATE)
+        .with_variable_update_max_frequency(Some(VARIABLE_FRAMERATE_CAP))
+        .with_fixed_update_channel(SLOW_CHANNEL, 1, Some(1))
+        .build::<ApplicationImpl>()
+        .expect("Invalid application settings");
+    application.add_layer(FpsLayer::default());
+    match application.run() {
+        Ok(status) => println!(
+            "The application exited ({:?}), {}",
+            status.reason(),
+            status.frame_stats()
+        ),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
 }
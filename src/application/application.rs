@@ -1,187 +1,719 @@
 extern crate winit;
 
-use std::collections::BTreeMap;
+use std::collections::VecDeque;
 
+use winit::platform::desktop::EventLoopExtDesktop;
+
+use super::{
+    poll_task, ApplicationBuilder, ApplicationError, ApplicationHandle, CallbackCategory,
+    ErrorRateLimit, ExitReason, ExitStatus, FixedUpdateChannel, FrameLimiter, FrameStats,
+    FrameStatsRecorder, JobMessage, LayerId, LoopMode, PanicPolicy, SharedClock, SimulationClock,
+    TimeBase, TimerAction,
+};
 use crate::{
     event::{
-        keyboard::ScanCode, ControlFlow, DeviceEvent, DeviceId, ElementState, Event, EventHandler,
-        EventLoop, WindowEvent,
+        keyboard::KeyboardState, ControlFlow, DeviceEvent, ElementState, ErrorResponse, Event,
+        EventContext, EventHandler, EventLoop, EventLoopAnyThread, EventLoopProxy,
+        EventLoopWindowTarget, ExitResponse, Layer, LayerCallback, LayerResponse, LoopRequests,
+        RawEventResponse, UserEvent, WindowEvent,
     },
-    window::{PhysicalPosition, WindowId},
+    window::PhysicalPosition,
 };
 
-#[derive(Clone)]
-pub struct Application<EventHandlerType, Error, CustomEvent>
+pub struct Application<EventHandlerType>
 where
-    EventHandlerType: EventHandler<Error, CustomEvent> + 'static,
-    Error: std::fmt::Display + std::error::Error + 'static,
-    CustomEvent: 'static,
+    EventHandlerType: EventHandler + 'static,
 {
     keyboard_state: KeyboardState,
-    fixed_update_period: std::time::Duration,
-    variable_update_min_period: std::time::Duration,
-    last_fixed_update_time: std::time::Instant,
+    loop_mode: LoopMode,
+    loop_requests: LoopRequests,
+    proxy: Option<EventLoopProxy<EventHandlerType::CustomEvent>>,
+    frame_count: u64,
+    fixed_update_count: u64,
+    any_thread: bool,
+    max_time_debt: Option<std::time::Duration>,
+    error_rate_limit: Option<ErrorRateLimit>,
+    panic_policy: PanicPolicy,
+    current_callback: &'static str,
+    clock: SharedClock,
+    handle: ApplicationHandle<EventHandlerType::CustomEvent>,
+    fixed_update_channels: Vec<FixedUpdateChannel>,
+    frame_limiter_spin_time: std::time::Duration,
     last_variable_update_time: std::time::Instant,
-    p0: std::marker::PhantomData<Error>,
-    p1: std::marker::PhantomData<CustomEvent>,
-    p2: std::marker::PhantomData<EventHandlerType>,
+    last_variable_update_simulation_time: std::time::Duration,
+    layers: Vec<(LayerId, Box<dyn Layer<EventHandlerType>>)>,
+    // The application doesn't own the handler, and it must stay Send even if the handler isn't,
+    // so that it can be moved to the thread it will run on.
+    p: std::marker::PhantomData<fn() -> EventHandlerType>,
 }
 
-impl<EventHandlerType, Error, CustomEvent> Application<EventHandlerType, Error, CustomEvent>
+impl<EventHandlerType> Application<EventHandlerType>
 where
-    EventHandlerType: EventHandler<Error, CustomEvent> + 'static,
-    Error: std::fmt::Display + std::error::Error + 'static,
-    CustomEvent: 'static,
+    EventHandlerType: EventHandler + 'static,
 {
+    // Shorthand for the builder defaults, panicking on invalid frequencies.
     pub fn new(
         fixed_update_frequency_hz: u64,
         variable_update_max_frequency_hz: Option<u64>,
     ) -> Self {
-        assert!(
-            fixed_update_frequency_hz > 0,
-            "The fixed update frequency must be higher than 0"
-        );
-        if let Some(v) = variable_update_max_frequency_hz {
-            assert!(v > 0, "The variable update frequency must be higher than 0");
-        }
+        ApplicationBuilder::new()
+            .with_fixed_update_frequency(fixed_update_frequency_hz)
+            .with_variable_update_max_frequency(variable_update_max_frequency_hz)
+            .build()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let fixed_update_period =
-            std::time::Duration::from_secs_f64(1. / fixed_update_frequency_hz as f64);
-        let variable_update_min_period = match variable_update_max_frequency_hz {
-            Some(v) => std::time::Duration::from_secs_f64(1. / v as f64),
-            None => std::time::Duration::from_secs_f64(0.),
-        };
-        let current_time = std::time::Instant::now();
+    pub(crate) fn from_builder(builder: ApplicationBuilder) -> Self {
+        let fixed_update_channels = builder
+            .all_fixed_update_channels()
+            .into_iter()
+            .map(FixedUpdateChannel::new)
+            .collect();
+        let current_time = builder.clock.now();
+        let handle = ApplicationHandle::new(
+            SimulationClock::new(current_time, builder.time_scale, builder.start_paused),
+            FrameStatsRecorder::new(builder.frame_stats_window),
+            FrameLimiter::new(builder.variable_update_max_frequency_hz),
+            builder.job_worker_thread_count(),
+            builder.fallback_theme.clone(),
+        );
 
         Self {
             keyboard_state: KeyboardState::new(),
-            fixed_update_period,
-            variable_update_min_period,
-            last_fixed_update_time: current_time,
+            loop_mode: builder.loop_mode,
+            loop_requests: LoopRequests::default(),
+            proxy: None,
+            frame_count: 0,
+            fixed_update_count: 0,
+            any_thread: builder.any_thread,
+            max_time_debt: builder.max_time_debt,
+            error_rate_limit: builder.error_rate_limit,
+            panic_policy: builder.panic_policy,
+            current_callback: "",
+            clock: builder.clock,
+            last_variable_update_simulation_time: std::time::Duration::from_secs(0),
+            handle,
+            fixed_update_channels,
+            frame_limiter_spin_time: builder.frame_limiter_spin_time,
             last_variable_update_time: current_time,
-            p0: std::marker::PhantomData,
-            p1: std::marker::PhantomData,
-            p2: std::marker::PhantomData,
+            layers: Vec::new(),
+            p: std::marker::PhantomData,
         }
     }
 
-    pub fn run(mut self) {
-        let event_loop = EventLoop::<EventHandlerType::CustomEvent>::with_user_event();
-        let mut event_handler = EventHandlerType::new(&event_loop)
-            .expect("Failed to initialize the application event handler");
+    // Layers see every callback before the handler, in the order kept by the application
+    // handle, which can also enable, disable and reorder them at runtime.
+    pub fn add_layer<L>(&mut self, layer: L) -> LayerId
+    where
+        L: Layer<EventHandlerType> + 'static,
+    {
+        let id = self.handle.lock().layer_stack.add();
+        self.layers.push((id, Box::new(layer)));
+        id
+    }
 
-        let current_time = std::time::Instant::now();
-        self.last_fixed_update_time = current_time;
-        self.last_variable_update_time = current_time;
+    pub fn run(mut self) -> Result<ExitStatus, ApplicationError<EventHandlerType::Error>> {
+        let event_loop = self.create_event_loop();
+        self.attach_event_loop(&event_loop);
+        let result = self.run_event_loop(event_loop);
+        self.detach_event_loop();
+        self.shut_down_jobs();
+        result
+    }
 
-        event_loop.run(move |event, _, control_flow| {
-            match self.handle_event(&mut event_handler, event) {
-                Ok(flow) => match flow {
-                    ControlFlow::Continue => *control_flow = winit::event_loop::ControlFlow::Poll,
-                    ControlFlow::Exit => *control_flow = winit::event_loop::ControlFlow::Exit,
-                },
-                Err(e) => {
-                    event_handler.on_error(e);
+    fn run_event_loop(
+        &mut self,
+        mut event_loop: EventLoop<EventHandlerType::CustomEvent>,
+    ) -> Result<ExitStatus, ApplicationError<EventHandlerType::Error>> {
+        let mut event_handler = EventHandlerType::new(&event_loop, &self.handle)
+            .map_err(ApplicationError::EventHandlerCreationFailed)?;
+
+        self.reset_clocks();
+        let mut run_state = RunState::new(true);
+        loop {
+            event_loop.run_return(|event, window_target, control_flow| {
+                // Custom events are taken from the queue even when they aren't dispatched.
+                let event = self.unwrap_user_event(event);
+                // When restarting, the rest of the iteration and the loop teardown are skipped.
+                if run_state.is_restarting() {
                     *control_flow = winit::event_loop::ControlFlow::Exit;
+                    return;
                 }
+                let event = match event {
+                    Some(event) => event,
+                    None if run_state.is_exiting() => {
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                    None => {
+                        *control_flow = self.next_control_flow();
+                        return;
+                    }
+                };
+                *control_flow = match self.dispatch(
+                    &mut event_handler,
+                    Some(window_target),
+                    event,
+                    &mut run_state,
+                ) {
+                    ControlFlow::Continue => self.next_control_flow(),
+                    ControlFlow::Exit => winit::event_loop::ControlFlow::Exit,
+                }
+            });
+            let reason = match run_state.take_restart_request() {
+                Some(reason) => reason,
+                None => break,
+            };
+            self.shut_down_for_restart(&mut event_handler, &event_loop, reason);
+            drop(event_handler);
+            self.reset_scheduled_work();
+            event_handler = match EventHandlerType::new(&event_loop, &self.handle) {
+                Ok(event_handler) => event_handler,
+                Err(e) => {
+                    run_state.fail_restart(e);
+                    drop(event_loop);
+                    return run_state.into_result(self.handle.frame_stats());
+                }
+            };
+            self.reset_clocks();
+        }
+
+        // The handler owns the windows, so they are destroyed before the event loop.
+        drop(event_handler);
+        drop(event_loop);
+        run_state.into_result(self.handle.frame_stats())
+    }
+
+    pub fn handle(&self) -> &ApplicationHandle<EventHandlerType::CustomEvent> {
+        &self.handle
+    }
+
+    // Other platforms can't run the event loop outside of the main thread.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    pub fn spawn(
+        mut self,
+    ) -> std::thread::JoinHandle<Result<ExitStatus, ApplicationError<EventHandlerType::Error>>>
+    where
+        EventHandlerType::Error: Send,
+        EventHandlerType::CustomEvent: Send,
+    {
+        self.any_thread = true;
+        std::thread::spawn(move || self.run())
+    }
+
+    pub(crate) fn create_event_loop(&self) -> EventLoop<EventHandlerType::CustomEvent> {
+        if self.any_thread {
+            EventLoop::<EventHandlerType::CustomEvent>::new_any_thread()
+        } else {
+            EventLoop::<EventHandlerType::CustomEvent>::with_user_event()
+        }
+    }
+
+    pub(crate) fn reset_clocks(&mut self) {
+        let current_time = self.clock.now();
+        let simulation_time = {
+            let mut state = self.handle.lock();
+            state.simulation_clock.reset(current_time);
+            state.frame_stats.reset();
+            state.simulation_clock.time()
+        };
+        for channel in self.fixed_update_channels.iter_mut() {
+            channel.reset(simulation_time);
+        }
+        self.last_variable_update_time = current_time;
+        self.last_variable_update_simulation_time = simulation_time;
+        self.frame_count = 0;
+        self.fixed_update_count = 0;
+    }
+
+    // Timers and tasks of a handler being restarted must not reach its replacement, and the
+    // clocks are reset so that the replacement schedules its timers from the start.
+    pub(crate) fn reset_scheduled_work(&mut self) {
+        let futures = {
+            let mut state = self.handle.lock();
+            state.scheduler.clear();
+            state.job_pool.cancel_all();
+            state.executor.clear()
+        };
+        drop(futures);
+        self.reset_clocks();
+    }
+
+    pub(crate) fn attach_event_loop(
+        &mut self,
+        event_loop: &EventLoop<EventHandlerType::CustomEvent>,
+    ) {
+        let proxy = EventLoopProxy::new(event_loop);
+        self.handle
+            .lock()
+            .loop_waker
+            .set(Some(Box::new(proxy.waker())));
+        self.handle.lock().proxy = Some(proxy.clone());
+        self.proxy = Some(proxy);
+        self.attach_executor();
+    }
+
+    // Tasks are polled on the thread running the application, so the executor is bound to it
+    // until the application stops. The futures left are dropped on the same thread.
+    pub(crate) fn attach_executor(&self) {
+        self.handle.lock().executor.bind();
+    }
+
+    pub(crate) fn detach_executor(&self) {
+        let futures = self.handle.lock().executor.unbind();
+        drop(futures);
+    }
+
+    // Without an attached event loop, no custom event can be waiting in the queue.
+    pub(crate) fn unwrap_user_event<'a>(
+        &self,
+        event: Event<'a, UserEvent<EventHandlerType::CustomEvent>>,
+    ) -> Option<Event<'a, EventHandlerType::CustomEvent>> {
+        self.proxy.as_ref()?.unwrap_user_event(event)
+    }
+
+    pub(crate) fn detach_event_loop(&mut self) {
+        self.detach_executor();
+        self.proxy = None;
+        let mut state = self.handle.lock();
+        state.proxy = None;
+        state.loop_waker.set(None);
+    }
+
+    // Jobs still running are cancelled, and their workers are joined before returning.
+    pub(crate) fn shut_down_jobs(&self) {
+        self.handle.lock().job_pool.shut_down();
+    }
+
+    #[cfg(feature = "catch-panic")]
+    pub(crate) fn dispatch(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        event: Event<EventHandlerType::CustomEvent>,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            self.dispatch_event(eh, window_target, event, run_state)
+        })) {
+            Ok(flow) => flow,
+            Err(payload) => self.handle_panic(eh, window_target, payload, run_state),
+        }
+    }
+
+    #[cfg(not(feature = "catch-panic"))]
+    pub(crate) fn dispatch(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        event: Event<EventHandlerType::CustomEvent>,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        self.dispatch_event(eh, window_target, event, run_state)
+    }
+
+    #[cfg_attr(not(feature = "catch-panic"), allow(dead_code))]
+    fn handle_panic(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        payload: Box<dyn std::any::Any + Send>,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        let callback = self.current_callback;
+        eh.on_panic(callback, payload.as_ref());
+        match self.panic_policy {
+            PanicPolicy::Continue => ControlFlow::Continue,
+            PanicPolicy::Exit => {
+                let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                    String::from(*message)
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    message.clone()
+                } else {
+                    String::from("Unknown panic payload")
+                };
+                run_state
+                    .error
+                    .get_or_insert(ApplicationError::EventHandlerPanicked { callback, message });
+                self.shut_down(eh, window_target, ExitReason::EventHandlerPanic, run_state)
             }
-        });
+        }
+    }
+
+    fn dispatch_event(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        event: Event<EventHandlerType::CustomEvent>,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        match event {
+            Event::LoopDestroyed => {
+                if !run_state.loop_destroyed {
+                    run_state.loop_destroyed = true;
+                    self.shut_down(eh, window_target, ExitReason::EventLoopDestroyed, run_state);
+                    self.current_callback = "on_event_loop_destroyed";
+                    if let Err(e) = eh.on_event_loop_destroyed(&mut self.context(window_target)) {
+                        self.fail(eh, window_target, e, run_state);
+                    }
+                }
+                return ControlFlow::Exit;
+            }
+            _ if run_state.is_exiting() => return ControlFlow::Exit,
+            Event::NewEvents(_) => run_state.skipping_frame = false,
+            _ if run_state.skipping_frame => return ControlFlow::Continue,
+            Event::MainEventsCleared => {
+                if let Some(reason) = run_state.pending_exit_reason.take() {
+                    if self.request_exit(eh, window_target, reason, run_state) == ControlFlow::Exit
+                    {
+                        return ControlFlow::Exit;
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        self.loop_requests = LoopRequests::default();
+        // Input and theme tracking follows the events as received, even when a layer or the raw
+        // event hook doesn't pass them on.
+        let is_repeat = self.update_keyboard_state(&event);
+        self.update_window_themes(&event);
+        // Only events reaching the typed dispatch can be the reason of an exit.
+        let mut exit_reason = ExitReason::EventHandlerRequest;
+        // Main events cleared is forwarded to the layers from the update, like to the handler.
+        let start_time = self.clock.now();
+        let layer_response = match &event {
+            Event::MainEventsCleared => Ok(None),
+            Event::UserEvent(custom_event) => {
+                self.run_layers(window_target, &LayerCallback::CustomEvent(custom_event))
+            }
+            event => self.run_layers(window_target, &LayerCallback::Event(event)),
+        };
+        let result = match layer_response {
+            Ok(Some(control_flow)) => {
+                self.record_callback(CallbackCategory::Event, start_time);
+                Ok(control_flow)
+            }
+            Ok(None) => {
+                self.current_callback = "on_raw_event";
+                let response = eh.on_raw_event(&mut self.context(window_target), event);
+                self.record_callback(CallbackCategory::Event, start_time);
+                match response {
+                    Ok(RawEventResponse::Dispatch(event)) => {
+                        exit_reason = exit_reason_for_event(&event);
+                        self.dispatch_typed_event(eh, window_target, event, is_repeat)
+                    }
+                    Ok(RawEventResponse::Consume(control_flow)) => Ok(control_flow),
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        // Requests made through the context take effect once the event has been handled. A
+        // restart request is ignored when the application cannot restart.
+        let loop_requests = std::mem::take(&mut self.loop_requests);
+        if loop_requests.restart && run_state.can_restart && result.is_ok() {
+            run_state.restart_reason = Some(ExitReason::EventHandlerRequest);
+            return ControlFlow::Exit;
+        }
+        let result = match result {
+            Ok(ControlFlow::Continue) if loop_requests.exit => Ok(ControlFlow::Exit),
+            result => result,
+        };
+        match result {
+            Ok(ControlFlow::Continue) => ControlFlow::Continue,
+            Ok(ControlFlow::Exit) => self.request_exit(eh, window_target, exit_reason, run_state),
+            Err(e) => self.fail(eh, window_target, e, run_state),
+        }
+    }
+
+    fn dispatch_typed_event(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        event: Event<EventHandlerType::CustomEvent>,
+        is_repeat: bool,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        self.current_callback = callback_name_for_event(&event);
+        // The update records its own callbacks, each in their category.
+        let category = match event {
+            Event::MainEventsCleared => None,
+            _ => Some(CallbackCategory::Event),
+        };
+        let start_time = self.clock.now();
+        let result = self.handle_event(eh, window_target, event, is_repeat);
+        if let Some(category) = category {
+            self.record_callback(category, start_time);
+        }
+        result
+    }
+
+    fn request_exit(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        reason: ExitReason,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        self.current_callback = "on_exit_requested";
+        match eh.on_exit_requested(&mut self.context(window_target), reason) {
+            Ok(ExitResponse::Accept) => self.shut_down(eh, window_target, reason, run_state),
+            Ok(ExitResponse::Veto) => ControlFlow::Continue,
+            Ok(ExitResponse::Defer) => {
+                run_state.pending_exit_reason = Some(reason);
+                ControlFlow::Continue
+            }
+            Err(e) => self.fail(eh, window_target, e, run_state),
+        }
+    }
+
+    fn fail(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        error: EventHandlerType::Error,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        self.current_callback = "on_error";
+        let mut response = eh.on_error(&error);
+        if response != ErrorResponse::Exit && self.error_rate_limit_exceeded(run_state) {
+            response = ErrorResponse::Exit;
+        }
+        match response {
+            ErrorResponse::Continue => ControlFlow::Continue,
+            ErrorResponse::SkipFrame => {
+                run_state.skipping_frame = true;
+                ControlFlow::Continue
+            }
+            ErrorResponse::Restart if run_state.can_restart => {
+                run_state.restart_reason = Some(ExitReason::EventHandlerError);
+                ControlFlow::Exit
+            }
+            ErrorResponse::Restart | ErrorResponse::Exit => {
+                run_state
+                    .error
+                    .get_or_insert(ApplicationError::EventHandlerFailed(error));
+                self.shut_down(eh, window_target, ExitReason::EventHandlerError, run_state)
+            }
+        }
+    }
+
+    fn error_rate_limit_exceeded(&self, run_state: &mut RunState<EventHandlerType::Error>) -> bool {
+        match self.error_rate_limit {
+            Some(limit) => {
+                let current_time = self.clock.now();
+                run_state.error_times.push_back(current_time);
+                while let Some(t) = run_state.error_times.front() {
+                    if current_time - *t > limit.period {
+                        run_state.error_times.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+                run_state.error_times.len() > limit.max_errors as usize
+            }
+            None => false,
+        }
+    }
+
+    fn shut_down(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        reason: ExitReason,
+        run_state: &mut RunState<EventHandlerType::Error>,
+    ) -> ControlFlow {
+        if run_state.exit_reason.is_none() {
+            run_state.exit_reason = Some(reason);
+            self.current_callback = "on_shutdown";
+            if let Err(e) = eh.on_shutdown(&mut self.context(window_target), reason) {
+                self.current_callback = "on_error";
+                eh.on_error(&e);
+                run_state
+                    .error
+                    .get_or_insert(ApplicationError::EventHandlerFailed(e));
+            }
+        }
+        ControlFlow::Exit
+    }
+
+    // The handler being replaced is notified, but it can't stop the restart anymore.
+    pub(crate) fn shut_down_for_restart(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: &EventLoopWindowTarget<EventHandlerType::CustomEvent>,
+        reason: ExitReason,
+    ) {
+        self.current_callback = "on_shutdown";
+        if let Err(e) = eh.on_shutdown(&mut self.context(Some(window_target)), reason) {
+            self.current_callback = "on_error";
+            eh.on_error(&e);
+        }
+    }
+
+    fn next_control_flow(&self) -> winit::event_loop::ControlFlow {
+        // A frame rate cap turns polling into waiting for the next update.
+        let is_limited = self.handle.lock().frame_limiter.is_limited();
+        let wake_up_time = match self.loop_mode {
+            LoopMode::Poll if !is_limited => return winit::event_loop::ControlFlow::Poll,
+            LoopMode::Poll | LoopMode::WaitUntil => {
+                earliest(self.next_update_time(), self.next_timer_time())
+            }
+            LoopMode::Wait => self.next_timer_time(),
+        };
+        // The deadline is measured by the application clock, which may not be the real one,
+        // so it is converted to a real time point for the event loop.
+        match wake_up_time.and_then(|t| {
+            std::time::Instant::now().checked_add(t.saturating_duration_since(self.clock.now()))
+        }) {
+            Some(t) => winit::event_loop::ControlFlow::WaitUntil(t),
+            None => winit::event_loop::ControlFlow::Wait,
+        }
+    }
+
+    fn next_timer_time(&self) -> Option<std::time::Instant> {
+        let state = self.handle.lock();
+        let next_simulation_timer_time = state
+            .scheduler
+            .next_deadline(TimeBase::Simulation)
+            .and_then(|t| state.simulation_clock.time_until(t));
+        let next_real_timer_time = state
+            .scheduler
+            .next_deadline(TimeBase::Real)
+            .map(|t| state.simulation_clock.real_time_until(t));
+        earliest(next_simulation_timer_time, next_real_timer_time)
+    }
+
+    fn next_update_time(&self) -> Option<std::time::Instant> {
+        let next_fixed_update_time = self
+            .fixed_update_channels
+            .iter()
+            .map(|channel| channel.next_update_time())
+            .min()
+            .and_then(|t| self.handle.lock().simulation_clock.time_until(t));
+        // Without a frequency cap, the variable update runs whenever the loop wakes up anyway,
+        // so it doesn't need a deadline of its own. With one, the loop wakes up a little early and
+        // spins for the remaining time, as waiting is much less precise than spinning.
+        let frame_limiter = self.handle.lock().frame_limiter;
+        let next_variable_update_time = if frame_limiter.is_limited() {
+            let deadline = self.last_variable_update_time + frame_limiter.min_period();
+            Some(
+                deadline
+                    .checked_sub(self.frame_limiter_spin_time)
+                    .unwrap_or(deadline),
+            )
+        } else {
+            None
+        };
+        earliest(next_fixed_update_time, next_variable_update_time)
     }
 
     fn handle_event(
         &mut self,
         eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
         event: Event<EventHandlerType::CustomEvent>,
+        is_repeat: bool,
     ) -> Result<ControlFlow, EventHandlerType::Error> {
+        // The update calls into the handler several times, each with its own context.
+        if let Event::MainEventsCleared = event {
+            return self.update(eh, window_target);
+        }
+        let context = &mut self.context(window_target);
         match event {
-            Event::NewEvents(start_cause) => eh.on_new_events(start_cause),
+            Event::NewEvents(start_cause) => eh.on_new_events(context, start_cause),
 
-            Event::UserEvent(event) => eh.on_custom_event(event),
+            Event::UserEvent(event) => eh.on_custom_event(context, event),
 
-            Event::Suspended => eh.on_suspended(),
+            Event::Suspended => eh.on_suspended(context),
 
-            Event::Resumed => eh.on_resumed(),
+            Event::Resumed => eh.on_resumed(context),
 
-            Event::MainEventsCleared => self.update(eh),
+            Event::MainEventsCleared => unreachable!(),
 
-            Event::RedrawRequested(window_id) => eh.on_redraw_requested(window_id),
+            Event::RedrawRequested(window_id) => eh.on_redraw_requested(context, window_id),
 
-            Event::RedrawEventsCleared => eh.on_redraw_events_cleared(),
+            Event::RedrawEventsCleared => eh.on_redraw_events_cleared(context),
 
-            Event::LoopDestroyed => eh.on_event_loop_destroyed(),
+            Event::LoopDestroyed => eh.on_event_loop_destroyed(context),
 
             Event::WindowEvent { window_id, event } => match event {
-                WindowEvent::CloseRequested => eh.on_close_requested(window_id),
+                WindowEvent::CloseRequested => eh.on_close_requested(context, window_id),
 
-                WindowEvent::Destroyed => eh.on_destroyed(window_id),
+                WindowEvent::Destroyed => eh.on_destroyed(context, window_id),
 
                 WindowEvent::Focused(focused) => {
                     if focused {
-                        eh.on_focus_gained(window_id)
+                        eh.on_focus_gained(context, window_id)
                     } else {
-                        eh.on_focus_lost(window_id)
+                        eh.on_focus_lost(context, window_id)
                     }
                 }
 
-                WindowEvent::Resized(size) => eh.on_resized(window_id, size),
+                WindowEvent::Resized(size) => eh.on_resized(context, window_id, size),
 
                 WindowEvent::ScaleFactorChanged {
                     scale_factor,
                     new_inner_size,
-                } => eh.on_scale_factor_changed(window_id, scale_factor, new_inner_size),
+                } => eh.on_scale_factor_changed(context, window_id, scale_factor, new_inner_size),
 
-                WindowEvent::Moved(pos) => eh.on_moved(window_id, pos),
+                WindowEvent::Moved(pos) => eh.on_moved(context, window_id, pos),
 
-                WindowEvent::ReceivedCharacter(c) => eh.on_received_character(window_id, c),
+                WindowEvent::ReceivedCharacter(c) => {
+                    eh.on_received_character(context, window_id, c)
+                }
 
-                WindowEvent::DroppedFile(path) => eh.on_hovered_file_dropped(window_id, path),
+                WindowEvent::DroppedFile(path) => {
+                    eh.on_hovered_file_dropped(context, window_id, path)
+                }
 
-                WindowEvent::HoveredFile(path) => eh.on_hovered_file_entered(window_id, path),
+                WindowEvent::HoveredFile(path) => {
+                    eh.on_hovered_file_entered(context, window_id, path)
+                }
 
-                WindowEvent::HoveredFileCancelled => eh.on_hovered_file_left(window_id),
+                WindowEvent::HoveredFileCancelled => eh.on_hovered_file_left(context, window_id),
 
                 WindowEvent::KeyboardInput {
                     device_id,
                     input,
                     is_synthetic,
-                } => {
-                    let last_key_state =
-                        self.keyboard_state
-                            .key_state(Some(window_id), device_id, input.scancode);
-                    let is_repeat = *last_key_state == input.state;
-                    *last_key_state = input.state;
-                    match input.state {
-                        ElementState::Pressed => eh.on_key_pressed(
-                            window_id,
-                            device_id,
-                            input.scancode,
-                            input.virtual_keycode,
-                            is_synthetic,
-                            is_repeat,
-                        ),
-                        ElementState::Released => eh.on_key_released(
-                            window_id,
-                            device_id,
-                            input.scancode,
-                            input.virtual_keycode,
-                            is_synthetic,
-                        ),
-                    }
-                }
+                } => match input.state {
+                    ElementState::Pressed => eh.on_key_pressed(
+                        context,
+                        window_id,
+                        device_id,
+                        input.scancode,
+                        input.virtual_keycode,
+                        is_synthetic,
+                        is_repeat,
+                    ),
+                    ElementState::Released => eh.on_key_released(
+                        context,
+                        window_id,
+                        device_id,
+                        input.scancode,
+                        input.virtual_keycode,
+                        is_synthetic,
+                    ),
+                },
 
-                WindowEvent::ModifiersChanged(mods) => eh.on_modifiers_changed(window_id, mods),
+                WindowEvent::ModifiersChanged(mods) => {
+                    eh.on_modifiers_changed(context, window_id, mods)
+                }
 
                 WindowEvent::CursorMoved {
                     device_id,
                     position,
                     ..
-                } => eh.on_cursor_moved(window_id, device_id, position),
+                } => eh.on_cursor_moved(context, window_id, device_id, position),
 
                 WindowEvent::CursorEntered { device_id } => {
-                    eh.on_cursor_entered(window_id, device_id)
+                    eh.on_cursor_entered(context, window_id, device_id)
                 }
 
-                WindowEvent::CursorLeft { device_id } => eh.on_cursor_left(window_id, device_id),
+                WindowEvent::CursorLeft { device_id } => {
+                    eh.on_cursor_left(context, window_id, device_id)
+                }
 
                 WindowEvent::MouseInput {
                     device_id,
@@ -190,10 +722,10 @@ where
                     ..
                 } => match state {
                     ElementState::Pressed => {
-                        eh.on_mouse_button_pressed(window_id, device_id, button)
+                        eh.on_mouse_button_pressed(context, window_id, device_id, button)
                     }
                     ElementState::Released => {
-                        eh.on_mouse_button_released(window_id, device_id, button)
+                        eh.on_mouse_button_released(context, window_id, device_id, button)
                     }
                 },
 
@@ -202,9 +734,10 @@ where
                     delta,
                     phase,
                     ..
-                } => eh.on_scroll(window_id, device_id, delta, phase),
+                } => eh.on_scroll(context, window_id, device_id, delta, phase),
 
                 WindowEvent::Touch(touch) => eh.on_touch(
+                    context,
                     window_id,
                     touch.device_id,
                     touch.phase,
@@ -217,133 +750,731 @@ where
                     device_id,
                     axis,
                     value,
-                } => eh.on_axis_moved(window_id, device_id, axis, value),
+                } => eh.on_axis_moved(context, window_id, device_id, axis, value),
 
-                // Not universally supported.
-                WindowEvent::TouchpadPressure { .. } => Ok(ControlFlow::Continue),
+                WindowEvent::TouchpadPressure {
+                    device_id,
+                    pressure,
+                    stage,
+                } => eh.on_touchpad_pressure(context, window_id, device_id, pressure, stage),
 
-                // Not universally supported.
-                WindowEvent::ThemeChanged(_) => Ok(ControlFlow::Continue),
+                WindowEvent::ThemeChanged(theme) => eh.on_theme_changed(context, window_id, theme),
             },
 
             Event::DeviceEvent { device_id, event } => match event {
-                DeviceEvent::Added => eh.on_device_added(device_id),
+                DeviceEvent::Added => eh.on_device_added(context, device_id),
 
-                DeviceEvent::Removed => eh.on_device_removed(device_id),
+                DeviceEvent::Removed => eh.on_device_removed(context, device_id),
 
-                DeviceEvent::MouseMotion { delta } => {
-                    eh.on_device_cursor_moved(device_id, PhysicalPosition::new(delta.0, delta.1))
-                }
+                DeviceEvent::MouseMotion { delta } => eh.on_device_cursor_moved(
+                    context,
+                    device_id,
+                    PhysicalPosition::new(delta.0, delta.1),
+                ),
 
-                DeviceEvent::MouseWheel { delta } => eh.on_device_scroll(device_id, delta),
+                DeviceEvent::MouseWheel { delta } => eh.on_device_scroll(context, device_id, delta),
 
                 DeviceEvent::Motion { axis, value } => {
-                    eh.on_device_axis_moved(device_id, axis, value)
+                    eh.on_device_axis_moved(context, device_id, axis, value)
                 }
 
                 DeviceEvent::Button { button, state } => match state {
-                    ElementState::Pressed => eh.on_device_button_pressed(device_id, button),
-                    ElementState::Released => eh.on_device_button_released(device_id, button),
+                    ElementState::Pressed => {
+                        eh.on_device_button_pressed(context, device_id, button)
+                    }
+                    ElementState::Released => {
+                        eh.on_device_button_released(context, device_id, button)
+                    }
                 },
 
-                DeviceEvent::Key(input) => {
-                    let last_key_state =
-                        self.keyboard_state
-                            .key_state(None, device_id, input.scancode);
-                    let is_repeat = *last_key_state == input.state;
-                    *last_key_state = input.state;
-                    match input.state {
-                        ElementState::Pressed => eh.on_device_key_pressed(
-                            device_id,
-                            input.scancode,
-                            input.virtual_keycode,
-                            is_repeat,
-                        ),
-                        ElementState::Released => eh.on_device_key_released(
-                            device_id,
-                            input.scancode,
-                            input.virtual_keycode,
-                        ),
-                    }
-                }
+                DeviceEvent::Key(input) => match input.state {
+                    ElementState::Pressed => eh.on_device_key_pressed(
+                        context,
+                        device_id,
+                        input.scancode,
+                        input.virtual_keycode,
+                        is_repeat,
+                    ),
+                    ElementState::Released => eh.on_device_key_released(
+                        context,
+                        device_id,
+                        input.scancode,
+                        input.virtual_keycode,
+                    ),
+                },
 
-                DeviceEvent::Text { codepoint } => eh.on_device_text(device_id, codepoint),
+                DeviceEvent::Text { codepoint } => eh.on_device_text(context, device_id, codepoint),
             },
         }
     }
 
+    // Returns whether the event repeats the last state of the same key.
+    fn update_keyboard_state(&mut self, event: &Event<EventHandlerType::CustomEvent>) -> bool {
+        let (window_id, device_id, input) = match event {
+            Event::WindowEvent {
+                window_id,
+                event:
+                    WindowEvent::KeyboardInput {
+                        device_id, input, ..
+                    },
+            } => (Some(*window_id), *device_id, input),
+            Event::DeviceEvent {
+                device_id,
+                event: DeviceEvent::Key(input),
+            } => (None, *device_id, input),
+            _ => return false,
+        };
+        let last_key_state =
+            self.keyboard_state
+                .key_state_mut(window_id, device_id, input.scancode);
+        let is_repeat = *last_key_state == input.state;
+        *last_key_state = input.state;
+        is_repeat
+    }
+
+    fn update_window_themes(&mut self, event: &Event<EventHandlerType::CustomEvent>) {
+        if let Event::WindowEvent { window_id, event } = event {
+            match event {
+                WindowEvent::ThemeChanged(theme) => {
+                    self.handle
+                        .lock()
+                        .window_themes
+                        .insert(*window_id, theme.clone());
+                }
+                WindowEvent::Destroyed => {
+                    self.handle.lock().window_themes.remove(window_id);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn context<'a>(
+        &'a mut self,
+        window_target: Option<&'a EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+    ) -> EventContext<'a, EventHandlerType::CustomEvent> {
+        EventContext::new(
+            window_target,
+            self.proxy.as_ref(),
+            &self.handle,
+            &self.clock,
+            self.frame_count,
+            self.fixed_update_count,
+            &self.keyboard_state,
+            &mut self.loop_mode,
+            &mut self.loop_requests,
+        )
+    }
+
+    fn next_due_fixed_update_channel(&self, simulation_time: std::time::Duration) -> Option<usize> {
+        // Channels run in simulation time order, and in registration order when due at the same
+        // time.
+        self.fixed_update_channels
+            .iter()
+            .enumerate()
+            .filter(|(_, channel)| channel.is_due(simulation_time))
+            .min_by_key(|(_, channel)| channel.next_update_time())
+            .map(|(i, _)| i)
+    }
+
+    fn fixed_update(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        channel_index: usize,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        let channel = &self.fixed_update_channels[channel_index];
+        let (id, period) = (channel.id(), channel.period());
+        let start_time = self.clock.now();
+        let control_flow = self.call_handler(
+            eh,
+            window_target,
+            LayerCallback::FixedUpdate {
+                channel: id,
+                dt: period,
+            },
+            "on_fixed_update",
+            |eh, context| eh.on_fixed_update(context, id, period),
+        )?;
+        self.record_callback(CallbackCategory::FixedUpdate, start_time);
+        self.fixed_update_count += 1;
+        Ok(control_flow)
+    }
+
     fn update(
         &mut self,
         eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
     ) -> Result<ControlFlow, EventHandlerType::Error> {
-        let current_time = std::time::Instant::now();
+        self.frame_count += 1;
+        self.wait_for_variable_update();
+        let current_time = self.clock.now();
+        let (advanced_time, simulation_time, real_time) = {
+            let mut state = self.handle.lock();
+            state.simulation_clock.advance(current_time);
+            state.frame_stats.begin_frame(current_time);
+            let advanced_time = state.simulation_clock.time();
+            let fixed_steps = state.simulation_clock.take_pending_fixed_steps();
+            state
+                .simulation_clock
+                .skip(self.fixed_update_channels[0].period() * fixed_steps);
+            (
+                advanced_time,
+                state.simulation_clock.time(),
+                state.simulation_clock.real_time(),
+            )
+        };
+
+        if self.fire_timers(eh, window_target, simulation_time, real_time)? == ControlFlow::Exit {
+            return Ok(ControlFlow::Exit);
+        }
+        if self.poll_tasks(eh, window_target)? == ControlFlow::Exit {
+            return Ok(ControlFlow::Exit);
+        }
+        if self.process_job_messages(eh, window_target)? == ControlFlow::Exit {
+            return Ok(ControlFlow::Exit);
+        }
 
-        while current_time - self.last_fixed_update_time >= self.fixed_update_period {
-            match eh.on_fixed_update(self.fixed_update_period) {
-                Ok(v) => match v {
-                    ControlFlow::Exit => return Ok(ControlFlow::Exit),
-                    _ => (),
+        for channel in self.fixed_update_channels.iter_mut() {
+            channel.begin_frame();
+        }
+        if let Some(max_time_debt) = self.max_time_debt {
+            for channel in self.fixed_update_channels.iter_mut() {
+                channel.drop_updates(advanced_time, max_time_debt);
+            }
+        }
+        while let Some(i) = self.next_due_fixed_update_channel(advanced_time) {
+            let channel = &mut self.fixed_update_channels[i];
+            if channel.catch_up_limit_reached() {
+                channel.drop_updates(advanced_time, std::time::Duration::from_secs(0));
+                continue;
+            }
+            if self.fixed_update(eh, window_target, i)? == ControlFlow::Exit {
+                return Ok(ControlFlow::Exit);
+            }
+            self.fixed_update_channels[i].advance();
+        }
+        // The explicitly requested steps follow the elapsed time. Each channel runs the updates
+        // due in them, which are neither limited by the catch-up steps nor dropped as time debt.
+        while let Some(i) = self.next_due_fixed_update_channel(simulation_time) {
+            if self.fixed_update(eh, window_target, i)? == ControlFlow::Exit {
+                return Ok(ControlFlow::Exit);
+            }
+            self.fixed_update_channels[i].step();
+        }
+        {
+            let mut state = self.handle.lock();
+            for channel in self.fixed_update_channels.iter() {
+                state
+                    .frame_stats
+                    .record_catch_up_steps(channel.catch_up_steps());
+            }
+        }
+        for i in 0..self.fixed_update_channels.len() {
+            let channel = &self.fixed_update_channels[i];
+            let (id, dropped_time) = (channel.id(), channel.dropped_time());
+            if dropped_time.as_nanos() == 0 {
+                continue;
+            }
+            let control_flow = self.call_handler(
+                eh,
+                window_target,
+                LayerCallback::FixedUpdatesDropped {
+                    channel: id,
+                    dropped_time,
                 },
-                Err(e) => return Err(e),
-            };
-            self.last_fixed_update_time += self.fixed_update_period;
+                "on_fixed_updates_dropped",
+                |eh, context| eh.on_fixed_updates_dropped(context, id, dropped_time),
+            )?;
+            if control_flow == ControlFlow::Exit {
+                return Ok(ControlFlow::Exit);
+            }
         }
 
+        // The default channel accumulator always holds less than a period at this point.
+        let fixed_update_alpha = self.fixed_update_channels[0].alpha(simulation_time);
+
+        let variable_update_min_period = self.handle.lock().frame_limiter.min_period();
         let time_since_last_variable_update = current_time - self.last_variable_update_time;
-        if time_since_last_variable_update > self.variable_update_min_period {
-            match eh.on_variable_update(time_since_last_variable_update) {
-                Ok(v) => match v {
-                    ControlFlow::Exit => return Ok(ControlFlow::Exit),
-                    _ => (),
+        let variable_update_due = time_since_last_variable_update >= variable_update_min_period;
+        if variable_update_due {
+            let dt = simulation_time - self.last_variable_update_simulation_time;
+            let start_time = self.clock.now();
+            let control_flow = self.call_handler(
+                eh,
+                window_target,
+                LayerCallback::VariableUpdate {
+                    dt,
+                    fixed_update_alpha,
                 },
-                Err(e) => return Err(e),
+                "on_variable_update",
+                |eh, context| eh.on_variable_update(context, dt, fixed_update_alpha),
+            )?;
+            self.record_callback(CallbackCategory::VariableUpdate, start_time);
+            if control_flow == ControlFlow::Exit {
+                return Ok(ControlFlow::Exit);
             }
             self.last_variable_update_time = current_time;
+            self.last_variable_update_simulation_time = simulation_time;
+        }
+
+        let start_time = self.clock.now();
+        let control_flow = self.call_handler(
+            eh,
+            window_target,
+            LayerCallback::MainEventsCleared,
+            "on_main_events_cleared",
+            |eh, context| eh.on_main_events_cleared(context),
+        )?;
+        self.record_callback(CallbackCategory::Event, start_time);
+        if control_flow == ControlFlow::Exit {
+            return Ok(ControlFlow::Exit);
+        }
+
+        if variable_update_due {
+            self.check_frame_deadline(eh, window_target)
+        } else {
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    fn fire_timers(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        simulation_time: std::time::Duration,
+        real_time: std::time::Duration,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        let mut due_timers = self
+            .handle
+            .lock()
+            .scheduler
+            .take_due(simulation_time, real_time)
+            .into_iter();
+        while let Some((timer, mut action)) = due_timers.next() {
+            // A timer whose action panics counts as fired, so that it doesn't stay scheduled
+            // without an action.
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| match &mut action {
+                    TimerAction::Callback(callback) => {
+                        self.current_callback = "timer_callback";
+                        callback();
+                        Ok(ControlFlow::Continue)
+                    }
+                    TimerAction::Event(event) => {
+                        self.deliver_custom_event(eh, window_target, event())
+                    }
+                }));
+            let mut state = self.handle.lock();
+            state
+                .scheduler
+                .finish(timer, action, true, simulation_time, real_time);
+            if !matches!(result, Ok(Ok(ControlFlow::Continue))) {
+                // The remaining timers fire on the next update instead.
+                for (timer, action) in due_timers.by_ref() {
+                    state
+                        .scheduler
+                        .finish(timer, action, false, simulation_time, real_time);
+                }
+                drop(state);
+                match result {
+                    Ok(result) => return result,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                }
+            }
+        }
+        Ok(ControlFlow::Continue)
+    }
+
+    fn poll_tasks(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        let (canceled_futures, ready_tasks) = {
+            let mut state = self.handle.lock();
+            (state.executor.take_canceled(), state.executor.take_ready())
+        };
+        drop(canceled_futures);
+        let mut ready_tasks = ready_tasks.into_iter();
+        while let Some((task, mut future, waker)) = ready_tasks.next() {
+            self.current_callback = "task";
+            // A panicking task is dropped, as its future can't be polled again.
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    match poll_task(&mut future, &waker) {
+                        std::task::Poll::Pending => (false, Ok(ControlFlow::Continue)),
+                        std::task::Poll::Ready(None) => (true, Ok(ControlFlow::Continue)),
+                        std::task::Poll::Ready(Some(event)) => {
+                            (true, self.deliver_custom_event(eh, window_target, event))
+                        }
+                    }
+                }));
+            let completed = match &result {
+                Ok((completed, _)) => *completed,
+                Err(_) => true,
+            };
+            let future = self.handle.lock().executor.finish(task, future, completed);
+            drop(future);
+            if !matches!(result, Ok((_, Ok(ControlFlow::Continue)))) {
+                // The remaining tasks are polled on the next update instead.
+                let mut state = self.handle.lock();
+                let (futures, remaining_wakers): (Vec<_>, Vec<_>) = ready_tasks
+                    .map(|(task, future, waker)| {
+                        (state.executor.finish(task, future, false), waker)
+                    })
+                    .unzip();
+                drop(state);
+                drop(futures);
+                remaining_wakers.into_iter().for_each(|waker| waker.wake());
+                match result {
+                    Ok((_, result)) => return result,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                }
+            }
+        }
+        Ok(ControlFlow::Continue)
+    }
+
+    fn process_job_messages(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        let job_messages = self.handle.lock().job_pool.take_messages();
+        for message in job_messages {
+            // Messages left over after an exit are dropped, as the jobs are done anyway.
+            let control_flow = match message {
+                JobMessage::Progress(job, progress) => {
+                    let start_time = self.clock.now();
+                    let result = self.call_handler(
+                        eh,
+                        window_target,
+                        LayerCallback::JobProgress { job, progress },
+                        "on_job_progress",
+                        |eh, context| eh.on_job_progress(context, job, progress),
+                    );
+                    self.record_callback(CallbackCategory::Event, start_time);
+                    result?
+                }
+                JobMessage::Completed(job, event) => {
+                    let start_time = self.clock.now();
+                    let result = match self.run_layers(
+                        window_target,
+                        &LayerCallback::JobCompleted { job, event: &event },
+                    ) {
+                        Ok(Some(control_flow)) => Ok(control_flow),
+                        Ok(None) => {
+                            self.current_callback = "on_job_completed";
+                            eh.on_job_completed(&mut self.context(window_target), job, event)
+                        }
+                        Err(e) => Err(e),
+                    };
+                    self.record_callback(CallbackCategory::Event, start_time);
+                    result?
+                }
+                JobMessage::Failed(job) => {
+                    let start_time = self.clock.now();
+                    let result = self.call_handler(
+                        eh,
+                        window_target,
+                        LayerCallback::JobFailed { job },
+                        "on_job_failed",
+                        |eh, context| eh.on_job_failed(context, job),
+                    );
+                    self.record_callback(CallbackCategory::Event, start_time);
+                    result?
+                }
+            };
+            if control_flow == ControlFlow::Exit {
+                return Ok(ControlFlow::Exit);
+            }
+        }
+        Ok(ControlFlow::Continue)
+    }
+
+    fn deliver_custom_event(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        event: EventHandlerType::CustomEvent,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        let start_time = self.clock.now();
+        let result = match self.run_layers(window_target, &LayerCallback::CustomEvent(&event)) {
+            Ok(Some(control_flow)) => Ok(control_flow),
+            Ok(None) => {
+                self.current_callback = "on_custom_event";
+                eh.on_custom_event(&mut self.context(window_target), event)
+            }
+            Err(e) => Err(e),
+        };
+        self.record_callback(CallbackCategory::Event, start_time);
+        result
+    }
+
+    // The event loop wakes up shortly before the variable update is due, the rest is spun.
+    fn wait_for_variable_update(&self) {
+        // In wait mode the loop only wakes up on events, so there is no frame rate to limit.
+        let frame_limiter = self.handle.lock().frame_limiter;
+        if !frame_limiter.is_limited() || self.loop_mode == LoopMode::Wait {
+            return;
+        }
+        let deadline = self.last_variable_update_time + frame_limiter.min_period();
+        let current_time = self.clock.now();
+        if current_time < deadline && deadline - current_time <= self.frame_limiter_spin_time {
+            self.clock
+                .wait_until(deadline, self.frame_limiter_spin_time);
+        }
+    }
+
+    fn check_frame_deadline(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+    ) -> Result<ControlFlow, EventHandlerType::Error> {
+        let frame_limiter = self.handle.lock().frame_limiter;
+        if !frame_limiter.is_limited() || self.loop_mode == LoopMode::Wait {
+            return Ok(ControlFlow::Continue);
+        }
+        let deadline = self.last_variable_update_time + frame_limiter.min_period();
+        let current_time = self.clock.now();
+        if current_time <= deadline {
+            return Ok(ControlFlow::Continue);
+        }
+        self.handle.lock().frame_stats.record_missed_deadline();
+        let lateness = current_time - deadline;
+        let start_time = self.clock.now();
+        let control_flow = self.call_handler(
+            eh,
+            window_target,
+            LayerCallback::FrameDeadlineMissed { lateness },
+            "on_frame_deadline_missed",
+            |eh, context| eh.on_frame_deadline_missed(context, lateness),
+        )?;
+        self.record_callback(CallbackCategory::Event, start_time);
+        Ok(control_flow)
+    }
+
+    fn call_handler<F>(
+        &mut self,
+        eh: &mut EventHandlerType,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        layer_callback: LayerCallback<EventHandlerType::CustomEvent>,
+        callback_name: &'static str,
+        callback: F,
+    ) -> Result<ControlFlow, EventHandlerType::Error>
+    where
+        F: FnOnce(
+            &mut EventHandlerType,
+            &mut EventContext<EventHandlerType::CustomEvent>,
+        ) -> Result<ControlFlow, EventHandlerType::Error>,
+    {
+        if let Some(control_flow) = self.run_layers(window_target, &layer_callback)? {
+            return Ok(control_flow);
+        }
+        self.current_callback = callback_name;
+        callback(eh, &mut self.context(window_target))
+    }
+
+    // Layers are taken out while they run, so that they can get a context borrowing the
+    // application. A panicking layer is put back before the panic is propagated.
+    fn run_layers(
+        &mut self,
+        window_target: Option<&EventLoopWindowTarget<EventHandlerType::CustomEvent>>,
+        callback: &LayerCallback<EventHandlerType::CustomEvent>,
+    ) -> Result<Option<ControlFlow>, EventHandlerType::Error> {
+        if self.layers.is_empty() {
+            return Ok(None);
+        }
+        let order = self.handle.lock().layer_stack.enabled_layers();
+        let mut layers = std::mem::take(&mut self.layers);
+        self.current_callback = "on_layer_callback";
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            for id in order {
+                if let Some((_, layer)) = layers.iter_mut().find(|(layer_id, _)| *layer_id == id) {
+                    if let LayerResponse::Consume(control_flow) =
+                        layer.on_callback(&mut self.context(window_target), callback)?
+                    {
+                        return Ok(Some(control_flow));
+                    }
+                }
+            }
+            Ok(None)
+        }));
+        self.layers = layers;
+        match result {
+            Ok(result) => result,
+            Err(payload) => std::panic::resume_unwind(payload),
         }
+    }
 
-        eh.on_main_events_cleared()
+    fn record_callback(&self, category: CallbackCategory, start_time: std::time::Instant) {
+        let time = self.clock.now() - start_time;
+        self.handle
+            .lock()
+            .frame_stats
+            .record_callback(category, time);
     }
 }
 
-#[derive(Clone)]
-struct KeyboardState {
-    state: BTreeMap<(Option<WindowId>, DeviceId), [ElementState; 128]>,
+pub(crate) struct RunState<Error>
+where
+    Error: std::fmt::Display + std::error::Error + 'static,
+{
+    pending_exit_reason: Option<ExitReason>,
+    exit_reason: Option<ExitReason>,
+    error: Option<ApplicationError<Error>>,
+    error_times: VecDeque<std::time::Instant>,
+    skipping_frame: bool,
+    can_restart: bool,
+    restart_reason: Option<ExitReason>,
+    loop_destroyed: bool,
 }
 
-impl KeyboardState {
-    pub fn new() -> Self {
+impl<Error> RunState<Error>
+where
+    Error: std::fmt::Display + std::error::Error + 'static,
+{
+    pub fn new(can_restart: bool) -> Self {
         Self {
-            state: BTreeMap::new(),
+            pending_exit_reason: None,
+            exit_reason: None,
+            error: None,
+            error_times: VecDeque::new(),
+            skipping_frame: false,
+            can_restart,
+            restart_reason: None,
+            loop_destroyed: false,
         }
     }
 
-    pub fn key_state(
-        &mut self,
-        window_id: Option<WindowId>,
-        device_id: DeviceId,
-        scan_code: ScanCode,
-    ) -> &mut ElementState {
-        let key = (window_id, device_id);
-        if !self.state.contains_key(&key) {
-            self.state.insert(key, [ElementState::Released; 128]);
-        }
-        // Guaranteed to succeed due to the previous lines.
-        let keyboard_state = self.state.get_mut(&key).unwrap();
-        // Assuming at most a certain number of scancodes. It should be enough.
-        // Asserting just for safety.
-        let key_idx = scan_code as usize;
-        assert!(
-            key_idx < keyboard_state.len(),
-            "Invalid scan code {}",
-            key_idx
+    pub fn is_exiting(&self) -> bool {
+        self.exit_reason.is_some()
+    }
+
+    pub fn is_restarting(&self) -> bool {
+        self.restart_reason.is_some()
+    }
+
+    pub fn take_restart_request(&mut self) -> Option<ExitReason> {
+        let reason = self.restart_reason.take()?;
+        self.skipping_frame = false;
+        self.pending_exit_reason = None;
+        Some(reason)
+    }
+
+    pub fn fail_restart(&mut self, error: Error) {
+        self.error
+            .get_or_insert(ApplicationError::EventHandlerCreationFailed(error));
+        self.exit_reason
+            .get_or_insert(ExitReason::EventHandlerError);
+    }
+
+    pub fn into_result(
+        self,
+        frame_stats: FrameStats,
+    ) -> Result<ExitStatus, ApplicationError<Error>> {
+        let status = ExitStatus::new(
+            self.exit_reason.unwrap_or(ExitReason::EventLoopDestroyed),
+            frame_stats,
         );
-        &mut keyboard_state[key_idx]
+        match self.error {
+            Some(e) => Err(ApplicationError::Exited {
+                status: Box::new(status),
+                error: Box::new(e),
+            }),
+            None => Ok(status),
+        }
+    }
+}
+
+fn earliest(
+    a: Option<std::time::Instant>,
+    b: Option<std::time::Instant>,
+) -> Option<std::time::Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn callback_name_for_event<CustomEvent>(event: &Event<CustomEvent>) -> &'static str {
+    match event {
+        Event::NewEvents(_) => "on_new_events",
+        Event::UserEvent(_) => "on_custom_event",
+        Event::Suspended => "on_suspended",
+        Event::Resumed => "on_resumed",
+        Event::MainEventsCleared => "on_main_events_cleared",
+        Event::RedrawRequested(_) => "on_redraw_requested",
+        Event::RedrawEventsCleared => "on_redraw_events_cleared",
+        Event::LoopDestroyed => "on_event_loop_destroyed",
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::CloseRequested => "on_close_requested",
+            WindowEvent::Destroyed => "on_destroyed",
+            WindowEvent::Focused(true) => "on_focus_gained",
+            WindowEvent::Focused(false) => "on_focus_lost",
+            WindowEvent::Resized(_) => "on_resized",
+            WindowEvent::ScaleFactorChanged { .. } => "on_scale_factor_changed",
+            WindowEvent::Moved(_) => "on_moved",
+            WindowEvent::ReceivedCharacter(_) => "on_received_character",
+            WindowEvent::DroppedFile(_) => "on_hovered_file_dropped",
+            WindowEvent::HoveredFile(_) => "on_hovered_file_entered",
+            WindowEvent::HoveredFileCancelled => "on_hovered_file_left",
+            WindowEvent::KeyboardInput { input, .. } => match input.state {
+                ElementState::Pressed => "on_key_pressed",
+                ElementState::Released => "on_key_released",
+            },
+            WindowEvent::ModifiersChanged(_) => "on_modifiers_changed",
+            WindowEvent::CursorMoved { .. } => "on_cursor_moved",
+            WindowEvent::CursorEntered { .. } => "on_cursor_entered",
+            WindowEvent::CursorLeft { .. } => "on_cursor_left",
+            WindowEvent::MouseInput { state, .. } => match state {
+                ElementState::Pressed => "on_mouse_button_pressed",
+                ElementState::Released => "on_mouse_button_released",
+            },
+            WindowEvent::MouseWheel { .. } => "on_scroll",
+            WindowEvent::Touch(_) => "on_touch",
+            WindowEvent::AxisMotion { .. } => "on_axis_moved",
+            WindowEvent::TouchpadPressure { .. } => "on_touchpad_pressure",
+            WindowEvent::ThemeChanged(_) => "on_theme_changed",
+        },
+        Event::DeviceEvent { event, .. } => match event {
+            DeviceEvent::Added => "on_device_added",
+            DeviceEvent::Removed => "on_device_removed",
+            DeviceEvent::MouseMotion { .. } => "on_device_cursor_moved",
+            DeviceEvent::MouseWheel { .. } => "on_device_scroll",
+            DeviceEvent::Motion { .. } => "on_device_axis_moved",
+            DeviceEvent::Button { state, .. } => match state {
+                ElementState::Pressed => "on_device_button_pressed",
+                ElementState::Released => "on_device_button_released",
+            },
+            DeviceEvent::Key(input) => match input.state {
+                ElementState::Pressed => "on_device_key_pressed",
+                ElementState::Released => "on_device_key_released",
+            },
+            DeviceEvent::Text { .. } => "on_device_text",
+        },
+    }
+}
+
+fn exit_reason_for_event<CustomEvent>(event: &Event<CustomEvent>) -> ExitReason {
+    match event {
+        Event::WindowEvent {
+            window_id,
+            event: WindowEvent::CloseRequested,
+        } => ExitReason::CloseRequested(*window_id),
+        Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Destroyed,
+        } => ExitReason::WindowDestroyed(*window_id),
+        _ => ExitReason::EventHandlerRequest,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::application::{FixedUpdateChannelId, ManualClock};
 
     #[derive(Debug, PartialEq, Clone, Copy)]
     enum MyError {}
@@ -363,26 +1494,123 @@ mod tests {
     #[derive(Debug)]
     struct MyEventHandler {}
 
-    impl EventHandler<MyError, ()> for MyEventHandler {
+    impl EventHandler for MyEventHandler {
         type Error = MyError;
         type CustomEvent = ();
 
-        fn new(_: &EventLoop<()>) -> Result<Self, Self::Error> {
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
             Ok(Self {})
         }
 
-        fn on_fixed_update(&mut self, _: std::time::Duration) -> Result<ControlFlow, Self::Error> {
+        fn on_fixed_update(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _: FixedUpdateChannelId,
+            _: std::time::Duration,
+        ) -> Result<ControlFlow, Self::Error> {
             Ok(ControlFlow::Exit)
         }
     }
 
     #[test]
     fn application_creation() {
-        let _app = Application::<MyEventHandler, _, _>::new(10, Some(10));
+        let _app = ApplicationBuilder::new()
+            .with_fixed_update_frequency(10)
+            .with_variable_update_max_frequency(Some(10))
+            .build::<MyEventHandler>()
+            .unwrap();
+    }
+
+    #[test]
+    fn new() {
+        let app = Application::<MyEventHandler>::new(10, Some(20));
+        assert_eq!(app.handle().variable_update_max_frequency(), Some(20));
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_invalid_frequency() {
+        Application::<MyEventHandler>::new(0, None);
+    }
+
+    #[test]
+    fn next_control_flow() {
+        let app = ApplicationBuilder::new()
+            .with_fixed_update_frequency(10)
+            .with_variable_update_max_frequency(None)
+            .with_loop_mode(LoopMode::WaitUntil)
+            .with_clock(ManualClock::new())
+            .build::<MyEventHandler>()
+            .unwrap();
+        let before = std::time::Instant::now();
+        let control_flow = app.next_control_flow();
+        let after = std::time::Instant::now();
+        match control_flow {
+            winit::event_loop::ControlFlow::WaitUntil(deadline) => {
+                assert!(deadline >= before + std::time::Duration::from_millis(100));
+                assert!(deadline <= after + std::time::Duration::from_millis(100));
+            }
+            control_flow => panic!("Unexpected control flow {:?}", control_flow),
+        }
+
+        // Deadlines that can't be represented make the loop wait indefinitely.
+        app.handle().set_time_scale(1e-30).unwrap();
+        assert_eq!(
+            app.next_control_flow(),
+            winit::event_loop::ControlFlow::Wait
+        );
+
+        // With a frame rate cap, polling waits for the variable update, waking up a little early.
+        let app = ApplicationBuilder::new()
+            .with_fixed_update_frequency(1)
+            .with_variable_update_max_frequency(Some(10))
+            .with_frame_limiter_spin_time(std::time::Duration::from_millis(2))
+            .with_loop_mode(LoopMode::Poll)
+            .with_clock(ManualClock::new())
+            .build::<MyEventHandler>()
+            .unwrap();
+        let before = std::time::Instant::now();
+        let control_flow = app.next_control_flow();
+        let after = std::time::Instant::now();
+        match control_flow {
+            winit::event_loop::ControlFlow::WaitUntil(deadline) => {
+                assert!(deadline >= before + std::time::Duration::from_millis(98));
+                assert!(deadline <= after + std::time::Duration::from_millis(98));
+            }
+            control_flow => panic!("Unexpected control flow {:?}", control_flow),
+        }
+        app.handle()
+            .set_variable_update_max_frequency(None)
+            .unwrap();
+        assert_eq!(
+            app.next_control_flow(),
+            winit::event_loop::ControlFlow::Poll
+        );
     }
 
     #[test]
     fn run() {
-        Application::<MyEventHandler, _, _>::new(10, Some(10)).run();
+        let status = ApplicationBuilder::new()
+            .with_fixed_update_frequency(10)
+            .with_variable_update_max_frequency(Some(10))
+            .build::<MyEventHandler>()
+            .unwrap()
+            .run()
+            .unwrap();
+        assert_eq!(status.reason(), ExitReason::EventHandlerRequest);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    fn spawn() {
+        let status = ApplicationBuilder::new()
+            .with_fixed_update_frequency(10)
+            .build::<MyEventHandler>()
+            .unwrap()
+            .spawn()
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.reason(), ExitReason::EventHandlerRequest);
     }
 }
@@ -0,0 +1,371 @@
+use super::{
+    is_valid_frequency, is_valid_time_scale, Application, Clock, FixedUpdateChannelId,
+    FixedUpdateChannelSettings, LoopMode, RealClock, SharedClock,
+};
+use crate::{event::EventHandler, window::Theme};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ApplicationBuildError {
+    InvalidFixedUpdateFrequency(u64),
+    InvalidVariableUpdateMaxFrequency(u64),
+    InvalidMaxCatchUpSteps(u32),
+    InvalidTimeScale(f64),
+    InvalidErrorRateLimit(ErrorRateLimit),
+    InvalidFrameStatsWindow(std::time::Duration),
+    DuplicateFixedUpdateChannel(FixedUpdateChannelId),
+    InvalidJobWorkerThreads(usize),
+}
+
+impl std::fmt::Display for ApplicationBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplicationBuildError::InvalidFixedUpdateFrequency(v) => write!(
+                f,
+                "Invalid fixed update frequency {} (must be higher than 0, with a period of at least 1ns)",
+                v
+            ),
+            ApplicationBuildError::InvalidVariableUpdateMaxFrequency(v) => write!(
+                f,
+                "Invalid variable update max frequency {} (must be higher than 0, with a period of at least 1ns)",
+                v
+            ),
+            ApplicationBuildError::InvalidMaxCatchUpSteps(v) => write!(
+                f,
+                "Invalid max catch up steps {} (must be higher than 0)",
+                v
+            ),
+            ApplicationBuildError::InvalidTimeScale(v) => write!(
+                f,
+                "Invalid time scale {} (must be finite and not negative)",
+                v
+            ),
+            ApplicationBuildError::InvalidErrorRateLimit(v) => write!(
+                f,
+                "Invalid error rate limit {:?} (max errors and period must be higher than 0)",
+                v
+            ),
+            ApplicationBuildError::InvalidFrameStatsWindow(v) => write!(
+                f,
+                "Invalid frame stats window {:?} (must be higher than 0)",
+                v
+            ),
+            ApplicationBuildError::DuplicateFixedUpdateChannel(v) => {
+                write!(f, "Duplicate fixed update channel '{}'", v)
+            }
+            ApplicationBuildError::InvalidJobWorkerThreads(v) => write!(
+                f,
+                "Invalid job worker threads {} (must be higher than 0)",
+                v
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ApplicationBuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ErrorRateLimit {
+    pub max_errors: u32,
+    pub period: std::time::Duration,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum PanicPolicy {
+    #[default]
+    Exit,
+    Continue,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ApplicationBuilder {
+    pub(crate) fixed_update_frequency_hz: u64,
+    pub(crate) variable_update_max_frequency_hz: Option<u64>,
+    pub(crate) loop_mode: LoopMode,
+    pub(crate) max_catch_up_steps: Option<u32>,
+    pub(crate) fixed_update_channels: Vec<FixedUpdateChannelSettings>,
+    pub(crate) max_time_debt: Option<std::time::Duration>,
+    pub(crate) error_rate_limit: Option<ErrorRateLimit>,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) any_thread: bool,
+    pub(crate) start_paused: bool,
+    pub(crate) time_scale: f64,
+    pub(crate) clock: SharedClock,
+    pub(crate) frame_stats_window: std::time::Duration,
+    pub(crate) frame_limiter_spin_time: std::time::Duration,
+    pub(crate) job_worker_threads: Option<usize>,
+    pub(crate) fallback_theme: Theme,
+}
+
+impl ApplicationBuilder {
+    pub fn new() -> Self {
+        Self {
+            fixed_update_frequency_hz: 60,
+            variable_update_max_frequency_hz: None,
+            loop_mode: LoopMode::default(),
+            max_catch_up_steps: None,
+            fixed_update_channels: Vec::new(),
+            max_time_debt: None,
+            error_rate_limit: Some(ErrorRateLimit {
+                max_errors: 10,
+                period: std::time::Duration::from_secs(1),
+            }),
+            panic_policy: PanicPolicy::default(),
+            any_thread: false,
+            start_paused: false,
+            time_scale: 1.,
+            clock: SharedClock(std::sync::Arc::new(RealClock)),
+            frame_stats_window: std::time::Duration::from_secs(1),
+            frame_limiter_spin_time: std::time::Duration::from_millis(2),
+            job_worker_threads: None,
+            fallback_theme: Theme::Light,
+        }
+    }
+
+    pub fn with_fixed_update_frequency(mut self, frequency_hz: u64) -> Self {
+        self.fixed_update_frequency_hz = frequency_hz;
+        self
+    }
+
+    pub fn with_variable_update_max_frequency(mut self, frequency_hz: Option<u64>) -> Self {
+        self.variable_update_max_frequency_hz = frequency_hz;
+        self
+    }
+
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn with_max_catch_up_steps(mut self, max_steps: Option<u32>) -> Self {
+        self.max_catch_up_steps = max_steps;
+        self
+    }
+
+    pub fn with_fixed_update_channel(
+        mut self,
+        id: FixedUpdateChannelId,
+        frequency_hz: u64,
+        max_catch_up_steps: Option<u32>,
+    ) -> Self {
+        self.fixed_update_channels.push(FixedUpdateChannelSettings {
+            id,
+            frequency_hz,
+            max_catch_up_steps,
+        });
+        self
+    }
+
+    pub fn with_max_time_debt(mut self, max_time_debt: Option<std::time::Duration>) -> Self {
+        self.max_time_debt = max_time_debt;
+        self
+    }
+
+    pub fn with_error_rate_limit(mut self, limit: Option<ErrorRateLimit>) -> Self {
+        self.error_rate_limit = limit;
+        self
+    }
+
+    pub fn with_panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    // Other platforms can't run the event loop outside of the main thread.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    pub fn with_any_thread(mut self, any_thread: bool) -> Self {
+        self.any_thread = any_thread;
+        self
+    }
+
+    pub fn with_start_paused(mut self, start_paused: bool) -> Self {
+        self.start_paused = start_paused;
+        self
+    }
+
+    pub fn with_time_scale(mut self, time_scale: f64) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = SharedClock(std::sync::Arc::new(clock));
+        self
+    }
+
+    pub fn with_frame_stats_window(mut self, window: std::time::Duration) -> Self {
+        self.frame_stats_window = window;
+        self
+    }
+
+    pub fn with_frame_limiter_spin_time(mut self, spin_time: std::time::Duration) -> Self {
+        self.frame_limiter_spin_time = spin_time;
+        self
+    }
+
+    pub fn with_job_worker_threads(mut self, threads: Option<usize>) -> Self {
+        self.job_worker_threads = threads;
+        self
+    }
+
+    pub fn with_fallback_theme(mut self, theme: Theme) -> Self {
+        self.fallback_theme = theme;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ApplicationBuildError> {
+        let fixed_update_channels = self.all_fixed_update_channels();
+        for (i, channel) in fixed_update_channels.iter().enumerate() {
+            if !is_valid_frequency(channel.frequency_hz) {
+                return Err(ApplicationBuildError::InvalidFixedUpdateFrequency(
+                    channel.frequency_hz,
+                ));
+            }
+            if channel.max_catch_up_steps == Some(0) {
+                return Err(ApplicationBuildError::InvalidMaxCatchUpSteps(0));
+            }
+            if fixed_update_channels[..i]
+                .iter()
+                .any(|c| c.id == channel.id)
+            {
+                return Err(ApplicationBuildError::DuplicateFixedUpdateChannel(
+                    channel.id,
+                ));
+            }
+        }
+        if let Some(v) = self.variable_update_max_frequency_hz {
+            if !is_valid_frequency(v) {
+                return Err(ApplicationBuildError::InvalidVariableUpdateMaxFrequency(v));
+            }
+        }
+        if let Some(v) = self.error_rate_limit {
+            if v.max_errors == 0 || v.period.as_nanos() == 0 {
+                return Err(ApplicationBuildError::InvalidErrorRateLimit(v));
+            }
+        }
+        if self.frame_stats_window.as_nanos() == 0 {
+            return Err(ApplicationBuildError::InvalidFrameStatsWindow(
+                self.frame_stats_window,
+            ));
+        }
+        if self.job_worker_threads == Some(0) {
+            return Err(ApplicationBuildError::InvalidJobWorkerThreads(0));
+        }
+        if !is_valid_time_scale(self.time_scale) {
+            return Err(ApplicationBuildError::InvalidTimeScale(self.time_scale));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn all_fixed_update_channels(&self) -> Vec<FixedUpdateChannelSettings> {
+        let mut channels = vec![FixedUpdateChannelSettings {
+            id: FixedUpdateChannelId::DEFAULT,
+            frequency_hz: self.fixed_update_frequency_hz,
+            max_catch_up_steps: self.max_catch_up_steps,
+        }];
+        channels.extend_from_slice(&self.fixed_update_channels);
+        channels
+    }
+
+    // One thread is left to the event loop unless the count is set explicitly.
+    pub(crate) fn job_worker_thread_count(&self) -> usize {
+        self.job_worker_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get().saturating_sub(1))
+                .unwrap_or(1)
+                .max(1)
+        })
+    }
+
+    pub fn build<EventHandlerType>(
+        self,
+    ) -> Result<Application<EventHandlerType>, ApplicationBuildError>
+    where
+        EventHandlerType: EventHandler + 'static,
+    {
+        self.validate()?;
+        Ok(Application::from_builder(self))
+    }
+}
+
+impl Default for ApplicationBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings() {
+        assert_eq!(ApplicationBuilder::new().validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(0)
+                .validate(),
+            Err(ApplicationBuildError::InvalidFixedUpdateFrequency(0))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_variable_update_max_frequency(Some(0))
+                .validate(),
+            Err(ApplicationBuildError::InvalidVariableUpdateMaxFrequency(0))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(10_000_000_000)
+                .validate(),
+            Err(ApplicationBuildError::InvalidFixedUpdateFrequency(
+                10_000_000_000
+            ))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_variable_update_max_frequency(Some(10_000_000_000))
+                .validate(),
+            Err(ApplicationBuildError::InvalidVariableUpdateMaxFrequency(
+                10_000_000_000
+            ))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_max_catch_up_steps(Some(0))
+                .validate(),
+            Err(ApplicationBuildError::InvalidMaxCatchUpSteps(0))
+        );
+        assert_eq!(
+            ApplicationBuilder::new().with_time_scale(-1.).validate(),
+            Err(ApplicationBuildError::InvalidTimeScale(-1.))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_fixed_update_channel(FixedUpdateChannelId("ai"), 0, None)
+                .validate(),
+            Err(ApplicationBuildError::InvalidFixedUpdateFrequency(0))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_fixed_update_channel(FixedUpdateChannelId("ai"), 10, None)
+                .with_fixed_update_channel(FixedUpdateChannelId("ai"), 20, None)
+                .validate(),
+            Err(ApplicationBuildError::DuplicateFixedUpdateChannel(
+                FixedUpdateChannelId("ai")
+            ))
+        );
+        assert_eq!(
+            ApplicationBuilder::new()
+                .with_job_worker_threads(Some(0))
+                .validate(),
+            Err(ApplicationBuildError::InvalidJobWorkerThreads(0))
+        );
+    }
+}
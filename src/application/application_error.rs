@@ -0,0 +1,59 @@
+use super::ExitStatus;
+
+#[derive(Debug)]
+pub enum ApplicationError<E>
+where
+    E: std::fmt::Display + std::error::Error + 'static,
+{
+    EventHandlerCreationFailed(E),
+    EventHandlerFailed(E),
+    EventHandlerPanicked {
+        callback: &'static str,
+        message: String,
+    },
+    Exited {
+        status: Box<ExitStatus>,
+        error: Box<ApplicationError<E>>,
+    },
+}
+
+impl<E> std::fmt::Display for ApplicationError<E>
+where
+    E: std::fmt::Display + std::error::Error + 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplicationError::EventHandlerCreationFailed(e) => {
+                write!(f, "Failed to create the event handler ({})", e)
+            }
+            ApplicationError::EventHandlerFailed(e) => {
+                write!(f, "The event handler failed ({})", e)
+            }
+            ApplicationError::EventHandlerPanicked { callback, message } => write!(
+                f,
+                "The event handler panicked in '{}' ({})",
+                callback, message
+            ),
+            ApplicationError::Exited { status, error } => write!(
+                f,
+                "The application exited ({:?}) after an error ({})",
+                status.reason(),
+                error
+            ),
+        }
+    }
+}
+
+impl<E> std::error::Error for ApplicationError<E>
+where
+    E: std::fmt::Display + std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApplicationError::EventHandlerCreationFailed(e) => Some(e),
+            ApplicationError::EventHandlerFailed(e) => Some(e),
+            ApplicationError::EventHandlerPanicked { .. } => None,
+            ApplicationError::Exited { error, .. } => Some(error.as_ref()),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use super::{
+    Executor, FrameLimiter, FrameStats, FrameStatsRecorder, InvalidFrequency, InvalidTimeScale,
+    JobContext, JobHandle, JobPool, LayerId, LayerStack, LoopWaker, Scheduler, SimulationClock,
+    SpawnError, TaskHandle, Timer, TimerAction, TimerHandle,
+};
+use crate::{
+    event::EventLoopProxy,
+    window::{Theme, WindowId},
+};
+
+#[derive(Debug)]
+pub(crate) struct SharedState<CustomEvent: 'static> {
+    pub simulation_clock: SimulationClock,
+    pub frame_stats: FrameStatsRecorder,
+    pub frame_limiter: FrameLimiter,
+    pub scheduler: Scheduler<CustomEvent>,
+    pub executor: Executor<CustomEvent>,
+    pub job_pool: JobPool<CustomEvent>,
+    pub window_themes: HashMap<WindowId, Theme>,
+    pub fallback_theme: Theme,
+    pub layer_stack: LayerStack,
+    pub loop_waker: Arc<LoopWaker>,
+    pub proxy: Option<EventLoopProxy<CustomEvent>>,
+}
+
+pub struct ApplicationHandle<CustomEvent: 'static> {
+    shared_state: Arc<Mutex<SharedState<CustomEvent>>>,
+}
+
+impl<CustomEvent: 'static> ApplicationHandle<CustomEvent> {
+    pub(crate) fn new(
+        simulation_clock: SimulationClock,
+        frame_stats: FrameStatsRecorder,
+        frame_limiter: FrameLimiter,
+        job_worker_threads: usize,
+        fallback_theme: Theme,
+    ) -> Self {
+        let loop_waker = Arc::new(LoopWaker::default());
+        Self {
+            shared_state: Arc::new(Mutex::new(SharedState {
+                simulation_clock,
+                frame_stats,
+                frame_limiter,
+                scheduler: Scheduler::new(),
+                executor: Executor::new(Arc::clone(&loop_waker)),
+                job_pool: JobPool::new(job_worker_threads, Arc::clone(&loop_waker)),
+                window_themes: HashMap::new(),
+                fallback_theme,
+                layer_stack: LayerStack::new(),
+                loop_waker,
+                proxy: None,
+            })),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, SharedState<CustomEvent>> {
+        // The shared state is never left inconsistent by a panic, so poisoning can be ignored.
+        self.shared_state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Custom events sent through the proxy are dispatched like the other events of the loop,
+    // raw event hook included. Headless applications have no event loop, and so no proxy.
+    pub fn event_loop_proxy(&self) -> Option<EventLoopProxy<CustomEvent>> {
+        self.lock().proxy.clone()
+    }
+
+    pub fn simulation_time(&self) -> std::time::Duration {
+        self.lock().simulation_clock.time()
+    }
+
+    pub fn real_time(&self) -> std::time::Duration {
+        self.lock().simulation_clock.real_time()
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.lock().simulation_clock.time_scale()
+    }
+
+    pub fn set_time_scale(&self, time_scale: f64) -> Result<(), InvalidTimeScale> {
+        self.lock().simulation_clock.set_time_scale(time_scale)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock().simulation_clock.is_paused()
+    }
+
+    pub fn pause(&self) {
+        self.lock().simulation_clock.set_paused(true);
+    }
+
+    pub fn resume(&self) {
+        self.lock().simulation_clock.set_paused(false);
+    }
+
+    pub fn step_fixed_updates(&self, steps: u32) {
+        self.lock().simulation_clock.request_fixed_steps(steps);
+    }
+
+    pub fn variable_update_max_frequency(&self) -> Option<u64> {
+        self.lock().frame_limiter.max_frequency()
+    }
+
+    pub fn set_variable_update_max_frequency(
+        &self,
+        frequency_hz: Option<u64>,
+    ) -> Result<(), InvalidFrequency> {
+        self.lock().frame_limiter.set_max_frequency(frequency_hz)
+    }
+
+    pub fn frame_stats(&self) -> FrameStats {
+        self.lock().frame_stats.stats()
+    }
+
+    pub fn schedule(&self, timer: Timer<CustomEvent>) -> TimerHandle {
+        let mut state = self.lock();
+        let simulation_time = state.simulation_clock.time();
+        let real_time = state.simulation_clock.real_time();
+        state.scheduler.schedule(timer, simulation_time, real_time)
+    }
+
+    pub fn cancel_timer(&self, timer: TimerHandle) -> bool {
+        self.lock().scheduler.cancel(timer)
+    }
+
+    pub fn is_timer_scheduled(&self, timer: TimerHandle) -> bool {
+        self.lock().scheduler.is_scheduled(timer)
+    }
+
+    // Tasks are polled on the thread running the application, and can only be spawned from it.
+    pub fn spawn<F>(&self, future: F) -> Result<TaskHandle, SpawnError>
+    where
+        F: std::future::Future<Output = CustomEvent> + 'static,
+    {
+        self.lock()
+            .executor
+            .spawn(Box::pin(async move { Some(future.await) }))
+    }
+
+    pub fn spawn_detached<F>(&self, future: F) -> Result<TaskHandle, SpawnError>
+    where
+        F: std::future::Future<Output = ()> + 'static,
+    {
+        self.lock().executor.spawn(Box::pin(async move {
+            future.await;
+            None
+        }))
+    }
+
+    pub fn sleep(
+        &self,
+        duration: std::time::Duration,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let state = Arc::new(Mutex::new((false, None::<std::task::Waker>)));
+        let timer_state = Arc::clone(&state);
+        self.schedule(Timer::once(
+            duration,
+            TimerAction::callback(move || {
+                let mut timer_state = timer_state
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                timer_state.0 = true;
+                if let Some(waker) = timer_state.1.take() {
+                    waker.wake();
+                }
+            }),
+        ));
+        std::future::poll_fn(move |cx| {
+            let mut state = state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if state.0 {
+                std::task::Poll::Ready(())
+            } else {
+                state.1 = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        })
+    }
+
+    pub fn cancel_task(&self, task: TaskHandle) -> bool {
+        self.lock().executor.cancel(task)
+    }
+
+    pub fn is_task_running(&self, task: TaskHandle) -> bool {
+        self.lock().executor.is_running(task)
+    }
+
+    // Windows that haven't reported a theme yet, for example on platforms that never do, use the
+    // fallback theme set in the application builder.
+    pub fn window_theme(&self, window_id: WindowId) -> Theme {
+        let state = self.lock();
+        state
+            .window_themes
+            .get(&window_id)
+            .unwrap_or(&state.fallback_theme)
+            .clone()
+    }
+
+    pub fn layers(&self) -> Vec<LayerId> {
+        self.lock().layer_stack.layers()
+    }
+
+    pub fn is_layer_enabled(&self, layer: LayerId) -> bool {
+        self.lock().layer_stack.is_enabled(layer)
+    }
+
+    pub fn set_layer_enabled(&self, layer: LayerId, enabled: bool) -> bool {
+        self.lock().layer_stack.set_enabled(layer, enabled)
+    }
+
+    pub fn move_layer(&self, layer: LayerId, index: usize) -> bool {
+        self.lock().layer_stack.move_to(layer, index)
+    }
+
+    pub fn submit_job<F>(&self, job: F) -> JobHandle
+    where
+        F: FnOnce(&JobContext) -> CustomEvent + Send + 'static,
+        CustomEvent: Send,
+    {
+        self.lock().job_pool.submit(Box::new(job))
+    }
+
+    pub fn cancel_job(&self, job: JobHandle) -> bool {
+        self.lock().job_pool.cancel(job)
+    }
+
+    pub fn is_job_running(&self, job: JobHandle) -> bool {
+        self.lock().job_pool.is_running(job)
+    }
+}
+
+impl<CustomEvent: 'static> Clone for ApplicationHandle<CustomEvent> {
+    fn clone(&self) -> Self {
+        Self {
+            shared_state: Arc::clone(&self.shared_state),
+        }
+    }
+}
+
+impl<CustomEvent: 'static> std::fmt::Debug for ApplicationHandle<CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        f.debug_struct("ApplicationHandle")
+            .field("simulation_clock", &state.simulation_clock)
+            .field("frame_limiter", &state.frame_limiter)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, HeadlessApplication,
+        },
+        event::{ControlFlow, Event, EventContext, EventHandler, EventLoop, WindowEvent},
+        window::{Theme, WindowId},
+    };
+
+    #[derive(Debug, Default)]
+    struct Themes {
+        themes: Vec<Theme>,
+    }
+
+    impl EventHandler for Themes {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_theme_changed(
+            &mut self,
+            _context: &mut EventContext<()>,
+            _wid: WindowId,
+            theme: Theme,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.themes.push(theme);
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn window_themes() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut app = HeadlessApplication::<Themes>::from_builder(
+            ApplicationBuilder::new().with_fallback_theme(Theme::Dark),
+        );
+        assert_eq!(app.handle().window_theme(window_id), Theme::Dark);
+
+        app.dispatch(Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ThemeChanged(Theme::Light),
+        });
+        assert_eq!(app.event_handler().themes, vec![Theme::Light]);
+        assert_eq!(app.handle().window_theme(window_id), Theme::Light);
+
+        app.dispatch(Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Destroyed,
+        });
+        assert_eq!(app.handle().window_theme(window_id), Theme::Dark);
+    }
+}
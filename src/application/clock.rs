@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    fn now(&self) -> std::time::Instant;
+
+    // Sleeps until shortly before the deadline, then spins for the remaining time, since sleeping
+    // is much less precise than spinning but doesn't keep the CPU busy.
+    fn wait_until(&self, deadline: std::time::Instant, spin_time: std::time::Duration) {
+        let current_time = self.now();
+        if deadline > current_time + spin_time {
+            std::thread::sleep(deadline - current_time - spin_time);
+        }
+        while self.now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> std::time::Instant {
+        std::time::Instant::now()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<std::time::Instant>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(std::time::Instant::now())),
+        }
+    }
+
+    pub fn advance(&self, dt: std::time::Duration) {
+        *self.lock() += dt;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::time::Instant> {
+        self.now
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> std::time::Instant {
+        *self.lock()
+    }
+
+    fn wait_until(&self, deadline: std::time::Instant, _spin_time: std::time::Duration) {
+        let mut now = self.lock();
+        if *now < deadline {
+            *now = deadline;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SharedClock(pub Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn now(&self) -> std::time::Instant {
+        self.0.now()
+    }
+
+    pub fn wait_until(&self, deadline: std::time::Instant, spin_time: std::time::Duration) {
+        self.0.wait_until(deadline, spin_time)
+    }
+}
+
+impl PartialEq for SharedClock {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock() {
+        let clock = ManualClock::new();
+        let shared_clock = clock.clone();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        shared_clock.advance(std::time::Duration::from_millis(5));
+        assert_eq!(clock.now() - start, std::time::Duration::from_millis(5));
+    }
+}
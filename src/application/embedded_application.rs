@@ -0,0 +1,205 @@
+extern crate winit;
+
+use winit::platform::desktop::EventLoopExtDesktop;
+
+use super::{Application, ApplicationError, ApplicationHandle, ExitStatus, RunState};
+use crate::event::{ControlFlow, Event, EventHandler, EventLoop, EventLoopStartCause};
+
+pub struct EmbeddedApplication<EventHandlerType>
+where
+    EventHandlerType: EventHandler + 'static,
+{
+    application: Application<EventHandlerType>,
+    event_handler: EventHandlerType,
+    event_loop: EventLoop<EventHandlerType::CustomEvent>,
+    run_state: RunState<EventHandlerType::Error>,
+    started: bool,
+}
+
+impl<EventHandlerType> EmbeddedApplication<EventHandlerType>
+where
+    EventHandlerType: EventHandler + 'static,
+{
+    pub fn new(
+        mut application: Application<EventHandlerType>,
+    ) -> Result<Self, ApplicationError<EventHandlerType::Error>> {
+        let event_loop = application.create_event_loop();
+        application.attach_event_loop(&event_loop);
+        let event_handler = match EventHandlerType::new(&event_loop, application.handle()) {
+            Ok(event_handler) => event_handler,
+            Err(e) => {
+                application.detach_event_loop();
+                return Err(ApplicationError::EventHandlerCreationFailed(e));
+            }
+        };
+        application.reset_clocks();
+        Ok(Self {
+            application,
+            event_handler,
+            event_loop,
+            run_state: RunState::new(true),
+            started: false,
+        })
+    }
+
+    pub fn event_handler(&self) -> &EventHandlerType {
+        &self.event_handler
+    }
+
+    pub fn event_handler_mut(&mut self) -> &mut EventHandlerType {
+        &mut self.event_handler
+    }
+
+    pub fn handle(&self) -> &ApplicationHandle<EventHandlerType::CustomEvent> {
+        self.application.handle()
+    }
+
+    pub fn is_exiting(&self) -> bool {
+        self.run_state.is_exiting()
+    }
+
+    pub fn step(&mut self) -> ControlFlow {
+        if self.run_state.is_exiting() {
+            return ControlFlow::Exit;
+        }
+
+        let application = &mut self.application;
+        let event_handler = &mut self.event_handler;
+        let run_state = &mut self.run_state;
+        let started = self.started;
+        let mut flow = ControlFlow::Continue;
+        // Each call to run_return processes exactly one loop iteration, since the control flow
+        // is set to exit right away. The loop is destroyed at the end of every iteration, so
+        // the LoopDestroyed event is only forwarded to the handler in finish.
+        self.event_loop
+            .run_return(|event, window_target, control_flow| {
+                *control_flow = winit::event_loop::ControlFlow::Exit;
+                let event = application.unwrap_user_event(event);
+                if run_state.is_restarting() {
+                    return;
+                }
+                let event = match event {
+                    Some(Event::LoopDestroyed) | None => return,
+                    Some(Event::NewEvents(EventLoopStartCause::Init)) if started => {
+                        Event::NewEvents(EventLoopStartCause::Poll)
+                    }
+                    Some(event) => event,
+                };
+                if !run_state.is_exiting()
+                    && application.dispatch(event_handler, Some(window_target), event, run_state)
+                        == ControlFlow::Exit
+                {
+                    flow = ControlFlow::Exit;
+                }
+            });
+        self.started = true;
+
+        if let Some(reason) = self.run_state.take_restart_request() {
+            self.application.shut_down_for_restart(
+                &mut self.event_handler,
+                &self.event_loop,
+                reason,
+            );
+            self.application.reset_scheduled_work();
+            match EventHandlerType::new(&self.event_loop, self.application.handle()) {
+                Ok(event_handler) => {
+                    self.event_handler = event_handler;
+                    self.application.reset_clocks();
+                }
+                Err(e) => {
+                    self.run_state.fail_restart(e);
+                    return ControlFlow::Exit;
+                }
+            }
+        }
+        flow
+    }
+
+    pub fn finish(mut self) -> Result<ExitStatus, ApplicationError<EventHandlerType::Error>> {
+        self.application.dispatch(
+            &mut self.event_handler,
+            Some(&self.event_loop),
+            Event::LoopDestroyed,
+            &mut self.run_state,
+        );
+        let result = self
+            .run_state
+            .into_result(self.application.handle().frame_stats());
+        self.application.detach_event_loop();
+        self.application.shut_down_jobs();
+        drop(self.event_handler);
+        drop(self.event_loop);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{ApplicationBuilder, ApplicationHandle, ExitReason, FixedUpdateChannelId},
+        event::EventContext,
+    };
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    enum MyError {}
+
+    impl std::fmt::Display for MyError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "MyError")
+        }
+    }
+
+    impl std::error::Error for MyError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            None
+        }
+    }
+
+    #[derive(Debug)]
+    struct MyEventHandler {
+        fixed_updates: u32,
+    }
+
+    impl EventHandler for MyEventHandler {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self { fixed_updates: 0 })
+        }
+
+        fn on_fixed_update(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _: FixedUpdateChannelId,
+            _: std::time::Duration,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.fixed_updates += 1;
+            if self.fixed_updates == 3 {
+                Ok(ControlFlow::Exit)
+            } else {
+                Ok(ControlFlow::Continue)
+            }
+        }
+    }
+
+    // Stepping needs a real event loop, and so a display.
+    #[test]
+    #[ignore]
+    fn step() {
+        let mut app = EmbeddedApplication::new(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .build::<MyEventHandler>()
+                .unwrap(),
+        )
+        .unwrap();
+        while app.step() == ControlFlow::Continue {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(app.event_handler().fixed_updates, 3);
+        let status = app.finish().unwrap();
+        assert_eq!(status.reason(), ExitReason::EventHandlerRequest);
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::ThreadId,
+};
+
+use super::LoopWaker;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct TaskHandle(u64);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SpawnError;
+
+impl std::fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tasks can only be spawned on the thread running the application"
+        )
+    }
+}
+
+impl std::error::Error for SpawnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+pub(crate) type TaskFuture<CustomEvent> = Pin<Box<dyn Future<Output = Option<CustomEvent>>>>;
+
+// Futures don't have to be Send, so they can only be reached from the thread the executor is
+// bound to. Unbinding the executor takes all of them out, so that none is left behind when the
+// application moves to another thread.
+struct LocalFutures<CustomEvent> {
+    thread: Option<ThreadId>,
+    futures: HashMap<TaskHandle, TaskFuture<CustomEvent>>,
+}
+
+// The futures are only accessed and dropped on the thread the executor is bound to.
+unsafe impl<CustomEvent> Send for LocalFutures<CustomEvent> {}
+
+impl<CustomEvent> LocalFutures<CustomEvent> {
+    fn get_mut(&mut self) -> Option<&mut HashMap<TaskHandle, TaskFuture<CustomEvent>>> {
+        if self.thread == Some(std::thread::current().id()) {
+            Some(&mut self.futures)
+        } else {
+            None
+        }
+    }
+}
+
+impl<CustomEvent> Drop for LocalFutures<CustomEvent> {
+    fn drop(&mut self) {
+        // Futures can't be dropped on another thread, so they are leaked instead.
+        if self.get_mut().is_none() {
+            std::mem::forget(std::mem::take(&mut self.futures));
+        }
+    }
+}
+
+struct ReadyQueue {
+    tasks: Mutex<Vec<TaskHandle>>,
+    loop_waker: Arc<LoopWaker>,
+}
+
+struct TaskWaker {
+    task: TaskHandle,
+    ready_queue: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        lock(&self.ready_queue.tasks).push(self.task);
+        self.ready_queue.loop_waker.wake_up();
+    }
+}
+
+// Futures taken out of the executor are returned to the caller, which drops them once the
+// executor isn't borrowed anymore, since dropping them can run arbitrary code.
+pub(crate) struct Executor<CustomEvent> {
+    tasks: HashSet<TaskHandle>,
+    futures: LocalFutures<CustomEvent>,
+    next_task: u64,
+    ready_queue: Arc<ReadyQueue>,
+}
+
+impl<CustomEvent> std::fmt::Debug for Executor<CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Executor")
+            .field("tasks", &self.tasks.len())
+            .finish()
+    }
+}
+
+impl<CustomEvent> Executor<CustomEvent> {
+    pub fn new(loop_waker: Arc<LoopWaker>) -> Self {
+        Self {
+            tasks: HashSet::new(),
+            futures: LocalFutures {
+                thread: None,
+                futures: HashMap::new(),
+            },
+            next_task: 0,
+            ready_queue: Arc::new(ReadyQueue {
+                tasks: Mutex::new(Vec::new()),
+                loop_waker,
+            }),
+        }
+    }
+
+    // The executor is bound to the thread running the application, and tasks can only be
+    // spawned while it is.
+    pub fn bind(&mut self) {
+        self.futures.thread = Some(std::thread::current().id());
+    }
+
+    pub fn unbind(&mut self) -> Vec<TaskFuture<CustomEvent>> {
+        let futures = self.clear();
+        self.futures.thread = None;
+        futures
+    }
+
+    pub fn clear(&mut self) -> Vec<TaskFuture<CustomEvent>> {
+        self.tasks.clear();
+        lock(&self.ready_queue.tasks).clear();
+        self.take_canceled()
+    }
+
+    pub fn spawn(&mut self, future: TaskFuture<CustomEvent>) -> Result<TaskHandle, SpawnError> {
+        let task = TaskHandle(self.next_task);
+        self.futures
+            .get_mut()
+            .ok_or(SpawnError)?
+            .insert(task, future);
+        self.next_task += 1;
+        self.tasks.insert(task);
+        waker(task, &self.ready_queue).wake();
+        Ok(task)
+    }
+
+    // The future of a canceled task is dropped by the thread polling it.
+    pub fn cancel(&mut self, task: TaskHandle) -> bool {
+        self.tasks.remove(&task)
+    }
+
+    pub fn is_running(&self, task: TaskHandle) -> bool {
+        self.tasks.contains(&task)
+    }
+
+    pub fn take_canceled(&mut self) -> Vec<TaskFuture<CustomEvent>> {
+        let tasks = &self.tasks;
+        match self.futures.get_mut() {
+            Some(futures) => {
+                let canceled_tasks: Vec<_> = futures
+                    .keys()
+                    .filter(|task| !tasks.contains(task))
+                    .copied()
+                    .collect();
+                canceled_tasks
+                    .iter()
+                    .filter_map(|task| futures.remove(task))
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // On other threads, the ready tasks are left in the queue.
+    pub fn take_ready(&mut self) -> Vec<(TaskHandle, TaskFuture<CustomEvent>, Waker)> {
+        let futures = match self.futures.get_mut() {
+            Some(futures) => futures,
+            None => return Vec::new(),
+        };
+        let mut ready_tasks = std::mem::take(&mut *lock(&self.ready_queue.tasks));
+        ready_tasks.sort();
+        ready_tasks.dedup();
+        let ready_queue = &self.ready_queue;
+        ready_tasks
+            .into_iter()
+            .filter_map(|task| {
+                let future = futures.remove(&task)?;
+                Some((task, future, waker(task, ready_queue)))
+            })
+            .collect()
+    }
+
+    // Returns the future if the executor doesn't keep it.
+    pub fn finish(
+        &mut self,
+        task: TaskHandle,
+        future: TaskFuture<CustomEvent>,
+        completed: bool,
+    ) -> Option<TaskFuture<CustomEvent>> {
+        if completed {
+            self.tasks.remove(&task);
+        } else if self.tasks.contains(&task) {
+            if let Some(futures) = self.futures.get_mut() {
+                futures.insert(task, future);
+                return None;
+            }
+        }
+        Some(future)
+    }
+}
+
+fn waker(task: TaskHandle, ready_queue: &Arc<ReadyQueue>) -> Waker {
+    Waker::from(Arc::new(TaskWaker {
+        task,
+        ready_queue: Arc::clone(ready_queue),
+    }))
+}
+
+pub(crate) fn poll_task<CustomEvent>(
+    future: &mut TaskFuture<CustomEvent>,
+    waker: &Waker,
+) -> Poll<Option<CustomEvent>> {
+    future.as_mut().poll(&mut Context::from_waker(waker))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, HeadlessApplication,
+            ManualClock,
+        },
+        event::{ControlFlow, Event, EventContext, EventHandler, EventLoop},
+    };
+
+    #[derive(Debug, Default)]
+    struct Signal {
+        state: Mutex<(bool, Option<Waker>)>,
+    }
+
+    impl Signal {
+        fn set(&self) {
+            let mut state = self.state.lock().unwrap();
+            state.0 = true;
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        }
+
+        async fn wait(self: Arc<Self>) {
+            std::future::poll_fn(|cx| {
+                let mut state = self.state.lock().unwrap();
+                if state.0 {
+                    Poll::Ready(())
+                } else {
+                    state.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            })
+            .await
+        }
+    }
+
+    fn poll_ready(executor: &mut Executor<u32>) -> Vec<(TaskHandle, Poll<Option<u32>>)> {
+        let mut results = Vec::new();
+        for (task, mut future, waker) in executor.take_ready() {
+            let result = poll_task(&mut future, &waker);
+            executor.finish(task, future, result.is_ready());
+            results.push((task, result));
+        }
+        results
+    }
+
+    #[test]
+    fn tasks() {
+        let mut executor = Executor::new(Arc::new(LoopWaker::default()));
+        executor.bind();
+        let signal = Arc::new(Signal::default());
+        let waiting_signal = Arc::clone(&signal);
+        // Futures stay on the thread that spawned them, so they don't have to be Send.
+        let task = executor
+            .spawn(Box::pin(async move {
+                let value = std::rc::Rc::new(1);
+                waiting_signal.wait().await;
+                Some(*value)
+            }))
+            .unwrap();
+        let canceled = executor
+            .spawn(Box::pin(async move {
+                Arc::new(Signal::default()).wait().await;
+                None
+            }))
+            .unwrap();
+
+        assert_eq!(
+            poll_ready(&mut executor),
+            vec![(task, Poll::Pending), (canceled, Poll::Pending)]
+        );
+        assert!(executor.is_running(task));
+        assert!(executor.cancel(canceled));
+        assert!(!executor.is_running(canceled));
+        assert_eq!(executor.take_canceled().len(), 1);
+        assert_eq!(poll_ready(&mut executor), vec![]);
+
+        std::thread::spawn(move || signal.set()).join().unwrap();
+        assert_eq!(
+            poll_ready(&mut executor),
+            vec![(task, Poll::Ready(Some(1)))]
+        );
+        assert!(!executor.is_running(task));
+    }
+
+    #[test]
+    fn thread_binding() {
+        let mut executor = Executor::<u32>::new(Arc::new(LoopWaker::default()));
+        assert_eq!(executor.spawn(Box::pin(async { None })), Err(SpawnError));
+
+        executor.bind();
+        let task = executor.spawn(Box::pin(async { Some(1) })).unwrap();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                assert_eq!(executor.spawn(Box::pin(async { None })), Err(SpawnError));
+                assert!(executor.take_ready().is_empty());
+            });
+        });
+        assert!(executor.is_running(task));
+
+        assert_eq!(executor.unbind().len(), 1);
+        assert!(!executor.is_running(task));
+        assert_eq!(executor.spawn(Box::pin(async { None })), Err(SpawnError));
+    }
+
+    #[derive(Debug, Default)]
+    struct CustomEvents {
+        custom_events: u32,
+    }
+
+    impl EventHandler for CustomEvents {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_custom_event(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _event: (),
+        ) -> Result<ControlFlow, Self::Error> {
+            self.custom_events += 1;
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn spawn_thread() {
+        let mut app = HeadlessApplication::<CustomEvents>::from_builder(ApplicationBuilder::new());
+        let handle = app.handle().clone();
+        let result = std::thread::spawn(move || handle.spawn_detached(async {}))
+            .join()
+            .unwrap();
+        assert_eq!(result, Err(SpawnError));
+        app.dispatch(Event::MainEventsCleared);
+
+        let task = app.handle().spawn(async {}).unwrap();
+        app.dispatch(Event::MainEventsCleared);
+        assert!(!app.handle().is_task_running(task));
+        assert_eq!(app.event_handler().custom_events, 1);
+    }
+
+    #[test]
+    fn sleep() {
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<CustomEvents>::from_builder(
+            ApplicationBuilder::new().with_clock(clock.clone()),
+        );
+        let handle = app.handle().clone();
+        app.handle()
+            .spawn(async move {
+                handle.sleep(std::time::Duration::from_millis(20)).await;
+                handle.sleep(std::time::Duration::from_millis(20)).await;
+            })
+            .unwrap();
+        app.dispatch(Event::MainEventsCleared);
+        clock.advance(std::time::Duration::from_millis(20));
+        app.dispatch(Event::MainEventsCleared);
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().custom_events, 0);
+        clock.advance(std::time::Duration::from_millis(20));
+        app.dispatch(Event::MainEventsCleared);
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().custom_events, 1);
+    }
+}
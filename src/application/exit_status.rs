@@ -0,0 +1,35 @@
+use super::FrameStats;
+use crate::window::WindowId;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExitReason {
+    CloseRequested(WindowId),
+    WindowDestroyed(WindowId),
+    EventHandlerRequest,
+    EventHandlerError,
+    EventHandlerPanic,
+    EventLoopDestroyed,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExitStatus {
+    reason: ExitReason,
+    frame_stats: FrameStats,
+}
+
+impl ExitStatus {
+    pub fn new(reason: ExitReason, frame_stats: FrameStats) -> Self {
+        Self {
+            reason,
+            frame_stats,
+        }
+    }
+
+    pub fn reason(&self) -> ExitReason {
+        self.reason
+    }
+
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+}
@@ -0,0 +1,358 @@
+use super::frequency_period;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct FixedUpdateChannelId(pub &'static str);
+
+impl FixedUpdateChannelId {
+    pub const DEFAULT: Self = Self("default");
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl std::fmt::Display for FixedUpdateChannelId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct FixedUpdateChannelSettings {
+    pub id: FixedUpdateChannelId,
+    pub frequency_hz: u64,
+    pub max_catch_up_steps: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FixedUpdateChannel {
+    id: FixedUpdateChannelId,
+    period: std::time::Duration,
+    max_catch_up_steps: Option<u32>,
+    last_update_time: std::time::Duration,
+    catch_up_steps: u32,
+    dropped_time: std::time::Duration,
+}
+
+impl FixedUpdateChannel {
+    pub fn new(settings: FixedUpdateChannelSettings) -> Self {
+        Self {
+            id: settings.id,
+            period: frequency_period(settings.frequency_hz),
+            max_catch_up_steps: settings.max_catch_up_steps,
+            last_update_time: std::time::Duration::from_secs(0),
+            catch_up_steps: 0,
+            dropped_time: std::time::Duration::from_secs(0),
+        }
+    }
+
+    pub fn id(&self) -> FixedUpdateChannelId {
+        self.id
+    }
+
+    pub fn period(&self) -> std::time::Duration {
+        self.period
+    }
+
+    pub fn next_update_time(&self) -> std::time::Duration {
+        self.last_update_time + self.period
+    }
+
+    pub fn is_due(&self, simulation_time: std::time::Duration) -> bool {
+        self.next_update_time() <= simulation_time
+    }
+
+    pub fn catch_up_steps(&self) -> u32 {
+        self.catch_up_steps
+    }
+
+    pub fn catch_up_limit_reached(&self) -> bool {
+        match self.max_catch_up_steps {
+            Some(max_steps) => self.catch_up_steps >= max_steps,
+            None => false,
+        }
+    }
+
+    pub fn dropped_time(&self) -> std::time::Duration {
+        self.dropped_time
+    }
+
+    pub fn alpha(&self, simulation_time: std::time::Duration) -> f64 {
+        (simulation_time - self.last_update_time).as_secs_f64() / self.period.as_secs_f64()
+    }
+
+    pub fn reset(&mut self, simulation_time: std::time::Duration) {
+        self.last_update_time = simulation_time;
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.catch_up_steps = 0;
+        self.dropped_time = std::time::Duration::from_secs(0);
+    }
+
+    pub fn advance(&mut self) {
+        self.last_update_time += self.period;
+        self.catch_up_steps += 1;
+    }
+
+    pub fn step(&mut self) {
+        self.last_update_time += self.period;
+    }
+
+    pub fn drop_updates(
+        &mut self,
+        simulation_time: std::time::Duration,
+        max_time_debt: std::time::Duration,
+    ) {
+        let time_debt = simulation_time - self.last_update_time;
+        if time_debt <= max_time_debt {
+            return;
+        }
+        let period = self.period.as_secs_f64();
+        // Only whole periods are dropped, and never more than the available ones, so that the
+        // last update time can't overtake the simulation time.
+        let dropped_steps = std::cmp::min(
+            ((time_debt - max_time_debt).as_secs_f64() / period).ceil() as u32,
+            (time_debt.as_secs_f64() / period).floor() as u32,
+        );
+        let dropped_time = self.period * dropped_steps;
+        self.last_update_time += dropped_time;
+        self.dropped_time += dropped_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, HeadlessApplication,
+            ManualClock,
+        },
+        event::{ControlFlow, Event, EventContext, EventHandler, EventLoop},
+    };
+
+    fn channel(frequency_hz: u64, max_catch_up_steps: Option<u32>) -> FixedUpdateChannel {
+        FixedUpdateChannel::new(FixedUpdateChannelSettings {
+            id: FixedUpdateChannelId::DEFAULT,
+            frequency_hz,
+            max_catch_up_steps,
+        })
+    }
+
+    #[test]
+    fn catch_up_limit() {
+        let ms = std::time::Duration::from_millis;
+        let mut channel = channel(1000, Some(2));
+        channel.begin_frame();
+        while channel.is_due(ms(20)) && !channel.catch_up_limit_reached() {
+            channel.advance();
+        }
+        assert_eq!(channel.next_update_time(), ms(3));
+        channel.drop_updates(ms(20), ms(0));
+        assert_eq!(channel.dropped_time(), ms(18));
+        assert!(!channel.is_due(ms(20)));
+
+        channel.begin_frame();
+        assert!(!channel.catch_up_limit_reached());
+        assert_eq!(channel.dropped_time(), ms(0));
+    }
+
+    #[test]
+    fn time_debt() {
+        let ms = std::time::Duration::from_millis;
+        let mut channel = channel(100, None);
+        channel.begin_frame();
+        channel.drop_updates(ms(15), ms(20));
+        assert_eq!(channel.dropped_time(), ms(0));
+
+        // Only whole periods are dropped, leaving at most the allowed debt.
+        channel.drop_updates(ms(55), ms(20));
+        assert_eq!(channel.dropped_time(), ms(40));
+        assert_eq!(channel.next_update_time(), ms(50));
+        assert!((channel.alpha(ms(55)) - 1.5).abs() < 1e-9);
+    }
+
+    #[derive(Debug, Default)]
+    struct FixedUpdates {
+        channels: Vec<FixedUpdateChannelId>,
+        dropped_time: std::time::Duration,
+        alphas: Vec<f64>,
+    }
+
+    impl EventHandler for FixedUpdates {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_fixed_update(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            channel: FixedUpdateChannelId,
+            _dt: std::time::Duration,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.channels.push(channel);
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_fixed_updates_dropped(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _channel: FixedUpdateChannelId,
+            dropped_time: std::time::Duration,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.dropped_time += dropped_time;
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_variable_update(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _dt: std::time::Duration,
+            fixed_update_alpha: f64,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.alphas.push(fixed_update_alpha);
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn fixed_update_count() {
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<FixedUpdates>::from_builder(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .with_clock(clock.clone()),
+        );
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().channels.len(), 0);
+
+        clock.advance(std::time::Duration::from_secs(1));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().channels.len(), 100);
+
+        clock.advance(std::time::Duration::from_millis(25));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().channels.len(), 102);
+
+        clock.advance(std::time::Duration::from_millis(5));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().channels.len(), 103);
+    }
+
+    #[test]
+    fn fixed_update_alpha() {
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<FixedUpdates>::from_builder(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .with_max_catch_up_steps(Some(1))
+                .with_clock(clock.clone()),
+        );
+        app.dispatch(Event::MainEventsCleared);
+        clock.advance(std::time::Duration::from_millis(5));
+        app.dispatch(Event::MainEventsCleared);
+        clock.advance(std::time::Duration::from_millis(10));
+        app.dispatch(Event::MainEventsCleared);
+
+        // Only whole periods are dropped, so the remainder is still part of the alpha.
+        clock.advance(std::time::Duration::from_millis(34));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().channels.len(), 2);
+        assert_eq!(
+            app.event_handler().dropped_time,
+            std::time::Duration::from_millis(20)
+        );
+        let expected_alphas = [0., 0.5, 0.5, 0.9];
+        assert_eq!(app.event_handler().alphas.len(), 4);
+        for (alpha, expected_alpha) in app
+            .event_handler()
+            .alphas
+            .iter()
+            .zip(expected_alphas.iter())
+        {
+            assert!((alpha - expected_alpha).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn fixed_update_channels() {
+        const AI: FixedUpdateChannelId = FixedUpdateChannelId("ai");
+        const NETWORK: FixedUpdateChannelId = FixedUpdateChannelId("network");
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<FixedUpdates>::from_builder(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .with_fixed_update_channel(AI, 25, None)
+                .with_fixed_update_channel(NETWORK, 50, Some(1))
+                .with_clock(clock.clone()),
+        );
+        clock.advance(std::time::Duration::from_millis(40));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(
+            app.event_handler().channels,
+            vec![
+                FixedUpdateChannelId::DEFAULT,
+                FixedUpdateChannelId::DEFAULT,
+                NETWORK,
+                FixedUpdateChannelId::DEFAULT,
+                FixedUpdateChannelId::DEFAULT,
+                AI
+            ]
+        );
+        assert_eq!(
+            app.event_handler().dropped_time,
+            std::time::Duration::from_millis(20)
+        );
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.handle().frame_stats().max_catch_up_steps(), 4);
+    }
+
+    #[test]
+    fn fixed_update_steps() {
+        const NETWORK: FixedUpdateChannelId = FixedUpdateChannelId("network");
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<FixedUpdates>::from_builder(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .with_max_catch_up_steps(Some(1))
+                .with_fixed_update_channel(NETWORK, 50, Some(1))
+                .with_max_time_debt(Some(std::time::Duration::from_millis(10)))
+                .with_start_paused(true)
+                .with_clock(clock.clone()),
+        );
+        clock.advance(std::time::Duration::from_millis(30));
+        app.handle().step_fixed_updates(3);
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(
+            app.event_handler().channels,
+            vec![
+                FixedUpdateChannelId::DEFAULT,
+                FixedUpdateChannelId::DEFAULT,
+                NETWORK,
+                FixedUpdateChannelId::DEFAULT
+            ]
+        );
+        assert_eq!(
+            app.event_handler().dropped_time,
+            std::time::Duration::from_secs(0)
+        );
+        assert_eq!(
+            app.handle().simulation_time(),
+            std::time::Duration::from_millis(30)
+        );
+
+        // The elapsed time is still subject to the catch-up limits and the time debt.
+        app.handle().resume();
+        clock.advance(std::time::Duration::from_millis(40));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().channels.len(), 5);
+        assert_eq!(
+            app.event_handler().dropped_time,
+            std::time::Duration::from_millis(70)
+        );
+    }
+}
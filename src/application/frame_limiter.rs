@@ -0,0 +1,205 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct InvalidFrequency(pub u64);
+
+impl std::fmt::Display for InvalidFrequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid frequency {} (must be higher than 0, with a period of at least 1ns)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidFrequency {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+pub(crate) fn frequency_period(frequency_hz: u64) -> std::time::Duration {
+    std::time::Duration::from_secs_f64(1. / frequency_hz as f64)
+}
+
+// Very high frequencies have periods that round to zero, on which the update loops would spin.
+pub(crate) fn is_valid_frequency(frequency_hz: u64) -> bool {
+    frequency_hz != 0 && frequency_period(frequency_hz).as_nanos() != 0
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct FrameLimiter {
+    max_frequency_hz: Option<u64>,
+    min_period: std::time::Duration,
+}
+
+impl FrameLimiter {
+    pub fn new(max_frequency_hz: Option<u64>) -> Self {
+        let min_period = match max_frequency_hz {
+            Some(v) => frequency_period(v),
+            None => std::time::Duration::from_secs(0),
+        };
+        Self {
+            max_frequency_hz,
+            min_period,
+        }
+    }
+
+    pub fn max_frequency(&self) -> Option<u64> {
+        self.max_frequency_hz
+    }
+
+    pub fn set_max_frequency(&mut self, frequency_hz: Option<u64>) -> Result<(), InvalidFrequency> {
+        if let Some(v) = frequency_hz {
+            if !is_valid_frequency(v) {
+                return Err(InvalidFrequency(v));
+            }
+        }
+        *self = Self::new(frequency_hz);
+        Ok(())
+    }
+
+    pub fn min_period(&self) -> std::time::Duration {
+        self.min_period
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.max_frequency_hz.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, Clock, HeadlessApplication,
+            ManualClock,
+        },
+        event::{ControlFlow, Event, EventContext, EventHandler, EventLoop},
+    };
+
+    #[test]
+    fn valid_frequency() {
+        assert!(!is_valid_frequency(0));
+        assert!(is_valid_frequency(1));
+        assert!(is_valid_frequency(1_000_000_000));
+        assert!(!is_valid_frequency(10_000_000_000));
+        assert!(!is_valid_frequency(u64::MAX));
+    }
+
+    #[test]
+    fn max_frequency() {
+        let mut limiter = FrameLimiter::new(Some(50));
+        assert!(limiter.is_limited());
+        assert_eq!(limiter.min_period(), std::time::Duration::from_millis(20));
+
+        assert_eq!(limiter.set_max_frequency(Some(0)), Err(InvalidFrequency(0)));
+        assert_eq!(limiter.max_frequency(), Some(50));
+
+        assert_eq!(
+            limiter.set_max_frequency(Some(10_000_000_000)),
+            Err(InvalidFrequency(10_000_000_000))
+        );
+        assert_eq!(limiter.max_frequency(), Some(50));
+
+        limiter.set_max_frequency(None).unwrap();
+        assert!(!limiter.is_limited());
+        assert_eq!(limiter.min_period(), std::time::Duration::from_secs(0));
+    }
+
+    #[derive(Debug, Default)]
+    struct VariableUpdates {
+        variable_updates: u32,
+        work: Option<(ManualClock, std::time::Duration)>,
+        missed_deadlines: Vec<std::time::Duration>,
+    }
+
+    impl EventHandler for VariableUpdates {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_variable_update(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _dt: std::time::Duration,
+            _fixed_update_alpha: f64,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.variable_updates += 1;
+            if let Some((clock, work_time)) = &self.work {
+                clock.advance(*work_time);
+            }
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_frame_deadline_missed(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            lateness: std::time::Duration,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.missed_deadlines.push(lateness);
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn frame_pacing() {
+        let clock = ManualClock::new();
+        let start_time = clock.now();
+        let mut app = HeadlessApplication::<VariableUpdates>::from_builder(
+            ApplicationBuilder::new()
+                .with_variable_update_max_frequency(Some(50))
+                .with_frame_limiter_spin_time(std::time::Duration::from_millis(2))
+                .with_clock(clock.clone()),
+        );
+
+        // The frame rate is only limited by waiting for the next variable update, which is
+        // left to the event loop apart from the last moments spun before the deadline.
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(clock.now(), start_time);
+        assert_eq!(app.event_handler().variable_updates, 0);
+        clock.advance(std::time::Duration::from_millis(19));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(
+            clock.now() - start_time,
+            std::time::Duration::from_millis(20)
+        );
+        assert_eq!(app.event_handler().variable_updates, 1);
+
+        clock.advance(std::time::Duration::from_millis(10));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(
+            clock.now() - start_time,
+            std::time::Duration::from_millis(30)
+        );
+        assert_eq!(app.event_handler().variable_updates, 1);
+
+        app.event_handler_mut().work = Some((clock.clone(), std::time::Duration::from_millis(25)));
+        clock.advance(std::time::Duration::from_millis(10));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(
+            clock.now() - start_time,
+            std::time::Duration::from_millis(65)
+        );
+        assert_eq!(
+            app.event_handler().missed_deadlines,
+            vec![std::time::Duration::from_millis(5)]
+        );
+        app.event_handler_mut().work = None;
+
+        assert_eq!(
+            app.handle().set_variable_update_max_frequency(Some(0)),
+            Err(InvalidFrequency(0))
+        );
+        app.handle()
+            .set_variable_update_max_frequency(Some(100))
+            .unwrap();
+        clock.advance(std::time::Duration::from_millis(10));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().variable_updates, 3);
+        assert_eq!(app.handle().frame_stats().missed_deadlines(), 1);
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CallbackCategory {
+    FixedUpdate,
+    VariableUpdate,
+    Event,
+}
+
+impl CallbackCategory {
+    const COUNT: usize = 3;
+
+    fn index(self) -> usize {
+        match self {
+            CallbackCategory::FixedUpdate => 0,
+            CallbackCategory::VariableUpdate => 1,
+            CallbackCategory::Event => 2,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FrameStats {
+    total_frame_count: u64,
+    frame_times: Vec<std::time::Duration>,
+    sorted_frame_times: Vec<std::time::Duration>,
+    fixed_updates: u64,
+    variable_updates: u64,
+    max_catch_up_steps: u32,
+    missed_deadlines: u64,
+    callback_times: [std::time::Duration; CallbackCategory::COUNT],
+}
+
+impl FrameStats {
+    pub fn total_frame_count(&self) -> u64 {
+        self.total_frame_count
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    pub fn elapsed_time(&self) -> std::time::Duration {
+        self.frame_times.iter().sum()
+    }
+
+    pub fn average_frame_time(&self) -> std::time::Duration {
+        match self.frame_count() {
+            0 => std::time::Duration::from_secs(0),
+            n => self.elapsed_time() / n as u32,
+        }
+    }
+
+    pub fn frame_time_percentile(&self, percentile: f64) -> std::time::Duration {
+        if self.sorted_frame_times.is_empty() {
+            return std::time::Duration::from_secs(0);
+        }
+        let rank = (percentile.clamp(0., 100.) / 100. * self.sorted_frame_times.len() as f64).ceil()
+            as usize;
+        self.sorted_frame_times[rank.max(1) - 1]
+    }
+
+    pub fn max_frame_time(&self) -> std::time::Duration {
+        self.frame_time_percentile(100.)
+    }
+
+    pub fn frames_per_second(&self) -> f64 {
+        self.per_second(self.frame_count() as u64)
+    }
+
+    pub fn fixed_updates_per_second(&self) -> f64 {
+        self.per_second(self.fixed_updates)
+    }
+
+    pub fn variable_updates_per_second(&self) -> f64 {
+        self.per_second(self.variable_updates)
+    }
+
+    pub fn max_catch_up_steps(&self) -> u32 {
+        self.max_catch_up_steps
+    }
+
+    pub fn missed_deadlines(&self) -> u64 {
+        self.missed_deadlines
+    }
+
+    pub fn callback_time(&self, category: CallbackCategory) -> std::time::Duration {
+        self.callback_times[category.index()]
+    }
+
+    fn per_second(&self, count: u64) -> f64 {
+        let elapsed_time = self.elapsed_time().as_secs_f64();
+        if elapsed_time > 0. {
+            count as f64 / elapsed_time
+        } else {
+            0.
+        }
+    }
+}
+
+impl std::fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} frames ({:.1} fps), frame time avg {:?} / p50 {:?} / p95 {:?} / p99 {:?} / max {:?}, \
+            {:.1} fixed updates/s, {:.1} variable updates/s, max catch up steps {}, \
+            missed deadlines {}, \
+            time in fixed updates {:?} / variable updates {:?} / events {:?}",
+            self.total_frame_count,
+            self.frames_per_second(),
+            self.average_frame_time(),
+            self.frame_time_percentile(50.),
+            self.frame_time_percentile(95.),
+            self.frame_time_percentile(99.),
+            self.max_frame_time(),
+            self.fixed_updates_per_second(),
+            self.variable_updates_per_second(),
+            self.max_catch_up_steps,
+            self.missed_deadlines,
+            self.callback_time(CallbackCategory::FixedUpdate),
+            self.callback_time(CallbackCategory::VariableUpdate),
+            self.callback_time(CallbackCategory::Event),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FrameSample {
+    frame_time: std::time::Duration,
+    fixed_updates: u32,
+    catch_up_steps: u32,
+    variable_updates: u32,
+    missed_deadline: bool,
+    callback_times: [std::time::Duration; CallbackCategory::COUNT],
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FrameStatsRecorder {
+    window: std::time::Duration,
+    samples: VecDeque<FrameSample>,
+    current_sample: FrameSample,
+    current_frame_start: Option<std::time::Instant>,
+    total_frame_count: u64,
+}
+
+impl FrameStatsRecorder {
+    pub fn new(window: std::time::Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            current_sample: FrameSample::default(),
+            current_frame_start: None,
+            total_frame_count: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.window);
+    }
+
+    pub fn begin_frame(&mut self, current_time: std::time::Instant) {
+        if let Some(start) = self.current_frame_start {
+            let mut sample = std::mem::take(&mut self.current_sample);
+            sample.frame_time = current_time - start;
+            self.samples.push_back(sample);
+            self.total_frame_count += 1;
+            let mut elapsed_time: std::time::Duration =
+                self.samples.iter().map(|s| s.frame_time).sum();
+            while elapsed_time > self.window && self.samples.len() > 1 {
+                if let Some(s) = self.samples.pop_front() {
+                    elapsed_time -= s.frame_time;
+                }
+            }
+        } else {
+            self.current_sample = FrameSample::default();
+        }
+        self.current_frame_start = Some(current_time);
+    }
+
+    pub fn record_callback(&mut self, category: CallbackCategory, time: std::time::Duration) {
+        match category {
+            CallbackCategory::FixedUpdate => self.current_sample.fixed_updates += 1,
+            CallbackCategory::VariableUpdate => self.current_sample.variable_updates += 1,
+            CallbackCategory::Event => (),
+        }
+        self.current_sample.callback_times[category.index()] += time;
+    }
+
+    // Each channel catches up on its own, so only the largest count of a frame is kept.
+    pub fn record_catch_up_steps(&mut self, steps: u32) {
+        self.current_sample.catch_up_steps =
+            std::cmp::max(self.current_sample.catch_up_steps, steps);
+    }
+
+    pub fn record_missed_deadline(&mut self) {
+        self.current_sample.missed_deadline = true;
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        let mut stats = FrameStats {
+            total_frame_count: self.total_frame_count,
+            ..FrameStats::default()
+        };
+        for sample in self.samples.iter() {
+            stats.frame_times.push(sample.frame_time);
+            stats.fixed_updates += sample.fixed_updates as u64;
+            stats.variable_updates += sample.variable_updates as u64;
+            stats.missed_deadlines += sample.missed_deadline as u64;
+            stats.max_catch_up_steps =
+                std::cmp::max(stats.max_catch_up_steps, sample.catch_up_steps);
+            for (total, time) in stats
+                .callback_times
+                .iter_mut()
+                .zip(sample.callback_times.iter())
+            {
+                *total += *time;
+            }
+        }
+        stats.sorted_frame_times = stats.frame_times.clone();
+        stats.sorted_frame_times.sort();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, HeadlessApplication,
+            ManualClock,
+        },
+        event::{Event, EventHandler, EventLoop},
+    };
+
+    #[test]
+    fn rolling_window() {
+        let mut recorder = FrameStatsRecorder::new(std::time::Duration::from_millis(100));
+        let mut current_time = std::time::Instant::now();
+        recorder.begin_frame(current_time);
+        for i in 1..=20 {
+            recorder.record_callback(
+                CallbackCategory::FixedUpdate,
+                std::time::Duration::from_millis(1),
+            );
+            recorder.record_catch_up_steps(1);
+            if i % 2 == 0 {
+                recorder.record_callback(
+                    CallbackCategory::FixedUpdate,
+                    std::time::Duration::from_millis(1),
+                );
+                recorder.record_catch_up_steps(2);
+            }
+            current_time += std::time::Duration::from_millis(i);
+            recorder.begin_frame(current_time);
+        }
+
+        let stats = recorder.stats();
+        assert_eq!(stats.total_frame_count(), 20);
+        assert_eq!(stats.frame_count(), 5);
+        assert_eq!(stats.elapsed_time(), std::time::Duration::from_millis(90));
+        assert_eq!(
+            stats.frame_time_percentile(50.),
+            std::time::Duration::from_millis(18)
+        );
+        assert_eq!(stats.max_frame_time(), std::time::Duration::from_millis(20));
+        assert_eq!(stats.max_catch_up_steps(), 2);
+        assert_eq!(
+            stats.callback_time(CallbackCategory::FixedUpdate),
+            std::time::Duration::from_millis(8)
+        );
+        assert!((stats.fixed_updates_per_second() - 8. / 0.09).abs() < 1e-9);
+    }
+
+    #[derive(Debug, Default)]
+    struct Idle;
+
+    impl EventHandler for Idle {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self)
+        }
+    }
+
+    #[test]
+    fn frame_stats() {
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<Idle>::from_builder(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .with_clock(clock.clone()),
+        );
+        for _ in 0..=100 {
+            app.dispatch(Event::MainEventsCleared);
+            clock.advance(std::time::Duration::from_millis(20));
+        }
+        let frame_stats = app.handle().frame_stats();
+        assert_eq!(frame_stats.total_frame_count(), 100);
+        assert_eq!(frame_stats.frame_count(), 50);
+        assert_eq!(
+            frame_stats.average_frame_time(),
+            std::time::Duration::from_millis(20)
+        );
+        assert_eq!(frame_stats.max_catch_up_steps(), 2);
+        assert!((frame_stats.frames_per_second() - 50.).abs() < 1e-9);
+        assert!((frame_stats.fixed_updates_per_second() - 100.).abs() < 1e-9);
+
+        let status = app.finish().unwrap();
+        assert_eq!(status.frame_stats(), &frame_stats);
+    }
+}
@@ -0,0 +1,585 @@
+use super::{Application, ApplicationError, ApplicationHandle, ExitStatus, RunState};
+use crate::event::{ControlFlow, Event, EventHandler};
+
+pub struct HeadlessApplication<EventHandlerType>
+where
+    EventHandlerType: EventHandler + 'static,
+{
+    application: Application<EventHandlerType>,
+    event_handler: EventHandlerType,
+    run_state: RunState<EventHandlerType::Error>,
+}
+
+impl<EventHandlerType> HeadlessApplication<EventHandlerType>
+where
+    EventHandlerType: EventHandler + 'static,
+{
+    pub fn new(
+        mut application: Application<EventHandlerType>,
+        event_handler: EventHandlerType,
+    ) -> Self {
+        application.attach_executor();
+        application.reset_clocks();
+        // Without an event loop a new handler can't be created, so restart requests are
+        // handled as exit requests.
+        Self {
+            application,
+            event_handler,
+            run_state: RunState::new(false),
+        }
+    }
+
+    // Without an event loop, the handler is created from the application handle only.
+    pub fn try_new<F>(
+        application: Application<EventHandlerType>,
+        create_event_handler: F,
+    ) -> Result<Self, ApplicationError<EventHandlerType::Error>>
+    where
+        F: FnOnce(
+            &ApplicationHandle<EventHandlerType::CustomEvent>,
+        ) -> Result<EventHandlerType, EventHandlerType::Error>,
+    {
+        let event_handler = create_event_handler(application.handle())
+            .map_err(ApplicationError::EventHandlerCreationFailed)?;
+        Ok(Self::new(application, event_handler))
+    }
+
+    pub fn event_handler(&self) -> &EventHandlerType {
+        &self.event_handler
+    }
+
+    pub fn event_handler_mut(&mut self) -> &mut EventHandlerType {
+        &mut self.event_handler
+    }
+
+    pub fn handle(&self) -> &ApplicationHandle<EventHandlerType::CustomEvent> {
+        self.application.handle()
+    }
+
+    pub fn is_exiting(&self) -> bool {
+        self.run_state.is_exiting()
+    }
+
+    pub fn dispatch(&mut self, event: Event<EventHandlerType::CustomEvent>) -> ControlFlow {
+        self.application
+            .dispatch(&mut self.event_handler, None, event, &mut self.run_state)
+    }
+
+    pub fn dispatch_all<'a, I>(&mut self, events: I) -> ControlFlow
+    where
+        I: IntoIterator<Item = Event<'a, EventHandlerType::CustomEvent>>,
+    {
+        for event in events {
+            if self.dispatch(event) == ControlFlow::Exit {
+                return ControlFlow::Exit;
+            }
+        }
+        ControlFlow::Continue
+    }
+
+    pub fn finish(mut self) -> Result<ExitStatus, ApplicationError<EventHandlerType::Error>> {
+        self.application.dispatch(
+            &mut self.event_handler,
+            None,
+            Event::LoopDestroyed,
+            &mut self.run_state,
+        );
+        self.application.detach_executor();
+        self.application.shut_down_jobs();
+        self.run_state
+            .into_result(self.application.handle().frame_stats())
+    }
+}
+
+#[cfg(test)]
+impl<EventHandlerType> HeadlessApplication<EventHandlerType>
+where
+    EventHandlerType: EventHandler + Default + 'static,
+{
+    pub(crate) fn from_builder(builder: super::ApplicationBuilder) -> Self {
+        Self::new(
+            builder.build::<EventHandlerType>().unwrap(),
+            EventHandlerType::default(),
+        )
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use crate::{
+        event::{DeviceId, ElementState, Event, KeyboardInput, WindowEvent},
+        window::WindowId,
+    };
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    pub struct MyError;
+
+    impl std::fmt::Display for MyError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "MyError")
+        }
+    }
+
+    impl std::error::Error for MyError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            None
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn key_event(window_id: WindowId, state: ElementState) -> Event<'static, ()> {
+        Event::WindowEvent {
+            window_id,
+            event: WindowEvent::KeyboardInput {
+                device_id: unsafe { DeviceId::dummy() },
+                input: KeyboardInput {
+                    scancode: 30,
+                    state,
+                    virtual_keycode: None,
+                    modifiers: Default::default(),
+                },
+                is_synthetic: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        test_utils::{key_event, MyError},
+        *,
+    };
+    use crate::{
+        application::{ApplicationBuilder, ApplicationHandle, ErrorRateLimit, ExitReason},
+        event::{
+            DeviceId, ElementState, ErrorResponse, EventContext, EventLoop, ExitResponse,
+            WindowEvent,
+        },
+        window::WindowId,
+    };
+
+    #[derive(Debug, Default)]
+    struct KeyPresses {
+        key_presses: u32,
+        key_repeats: u32,
+    }
+
+    impl EventHandler for KeyPresses {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_key_pressed(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            _device_id: DeviceId,
+            _scan_code: crate::event::keyboard::ScanCode,
+            _key_code: Option<crate::event::keyboard::KeyCode>,
+            _is_synthetic: bool,
+            is_repeat: bool,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.key_presses += 1;
+            if is_repeat {
+                self.key_repeats += 1;
+            }
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut app = HeadlessApplication::<KeyPresses>::from_builder(ApplicationBuilder::new());
+
+        let flow = app.dispatch_all(vec![
+            Event::NewEvents(crate::event::EventLoopStartCause::Init),
+            key_event(window_id, ElementState::Pressed),
+            key_event(window_id, ElementState::Pressed),
+            key_event(window_id, ElementState::Released),
+            key_event(window_id, ElementState::Pressed),
+            Event::MainEventsCleared,
+        ]);
+        assert_eq!(flow, ControlFlow::Continue);
+        assert_eq!(app.event_handler().key_presses, 3);
+        assert_eq!(app.event_handler().key_repeats, 1);
+
+        let flow = app.dispatch(Event::WindowEvent {
+            window_id,
+            event: WindowEvent::CloseRequested,
+        });
+        assert_eq!(flow, ControlFlow::Exit);
+        assert!(app.is_exiting());
+        assert_eq!(
+            app.dispatch(key_event(window_id, ElementState::Pressed)),
+            ControlFlow::Exit
+        );
+        assert_eq!(app.event_handler().key_presses, 3);
+
+        let status = app.finish().unwrap();
+        assert_eq!(status.reason(), ExitReason::CloseRequested(window_id));
+    }
+
+    #[derive(Debug, Default)]
+    struct ExitNegotiation {
+        exit_responses: Vec<ExitResponse>,
+        calls: Vec<&'static str>,
+    }
+
+    impl EventHandler for ExitNegotiation {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_exit_requested(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _reason: ExitReason,
+        ) -> Result<ExitResponse, Self::Error> {
+            self.calls.push("on_exit_requested");
+            Ok(self.exit_responses.pop().unwrap_or(ExitResponse::Accept))
+        }
+
+        fn on_shutdown(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _reason: ExitReason,
+        ) -> Result<(), Self::Error> {
+            self.calls.push("on_shutdown");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn exit_negotiation() {
+        let window_id = unsafe { WindowId::dummy() };
+        let close_requested = || Event::WindowEvent {
+            window_id,
+            event: WindowEvent::CloseRequested,
+        };
+        let mut app =
+            HeadlessApplication::<ExitNegotiation>::from_builder(ApplicationBuilder::new());
+        app.event_handler_mut().exit_responses = vec![
+            ExitResponse::Accept,
+            ExitResponse::Defer,
+            ExitResponse::Veto,
+        ];
+
+        assert_eq!(app.dispatch(close_requested()), ControlFlow::Continue);
+        assert_eq!(app.dispatch(close_requested()), ControlFlow::Continue);
+        assert_eq!(app.dispatch(Event::MainEventsCleared), ControlFlow::Exit);
+        assert_eq!(
+            app.event_handler().calls,
+            vec![
+                "on_exit_requested",
+                "on_exit_requested",
+                "on_exit_requested",
+                "on_shutdown"
+            ]
+        );
+
+        let status = app.finish().unwrap();
+        assert_eq!(status.reason(), ExitReason::CloseRequested(window_id));
+    }
+
+    #[derive(Debug, Default)]
+    struct ShutdownFailure;
+
+    impl EventHandler for ShutdownFailure {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self)
+        }
+
+        fn on_shutdown(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _reason: ExitReason,
+        ) -> Result<(), Self::Error> {
+            Err(MyError)
+        }
+    }
+
+    #[test]
+    fn shutdown_failure() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut app =
+            HeadlessApplication::<ShutdownFailure>::from_builder(ApplicationBuilder::new());
+        app.dispatch(Event::MainEventsCleared);
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(
+            app.dispatch(Event::WindowEvent {
+                window_id,
+                event: WindowEvent::CloseRequested,
+            }),
+            ControlFlow::Exit
+        );
+
+        match app.finish() {
+            Err(ApplicationError::Exited { status, error }) => {
+                assert_eq!(status.reason(), ExitReason::CloseRequested(window_id));
+                assert_eq!(status.frame_stats().total_frame_count(), 1);
+                match *error {
+                    ApplicationError::EventHandlerFailed(e) => assert_eq!(e, MyError),
+                    _ => panic!("Unexpected application error"),
+                }
+            }
+            _ => panic!("Unexpected application result"),
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct ErrorPolicy {
+        key_presses: u32,
+        error_response: Option<ErrorResponse>,
+        calls: Vec<&'static str>,
+    }
+
+    impl EventHandler for ErrorPolicy {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_key_pressed(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            _device_id: DeviceId,
+            _scan_code: crate::event::keyboard::ScanCode,
+            _key_code: Option<crate::event::keyboard::KeyCode>,
+            _is_synthetic: bool,
+            _is_repeat: bool,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.key_presses += 1;
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_hovered_file_dropped(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            _path: std::path::PathBuf,
+        ) -> Result<ControlFlow, Self::Error> {
+            Err(MyError)
+        }
+
+        fn on_error(&mut self, _error: &Self::Error) -> ErrorResponse {
+            self.calls.push("on_error");
+            self.error_response.unwrap_or(ErrorResponse::Exit)
+        }
+
+        fn on_shutdown(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _reason: ExitReason,
+        ) -> Result<(), Self::Error> {
+            self.calls.push("on_shutdown");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn error_policy() {
+        let window_id = unsafe { WindowId::dummy() };
+        let file_dropped = || Event::WindowEvent {
+            window_id,
+            event: WindowEvent::DroppedFile(std::path::PathBuf::from("file.txt")),
+        };
+        let mut app = HeadlessApplication::<ErrorPolicy>::from_builder(
+            ApplicationBuilder::new().with_error_rate_limit(Some(ErrorRateLimit {
+                max_errors: 2,
+                period: std::time::Duration::from_secs(60),
+            })),
+        );
+        app.event_handler_mut().error_response = Some(ErrorResponse::SkipFrame);
+
+        assert_eq!(app.dispatch(file_dropped()), ControlFlow::Continue);
+        assert_eq!(
+            app.dispatch(key_event(window_id, ElementState::Pressed)),
+            ControlFlow::Continue
+        );
+        assert_eq!(app.event_handler().key_presses, 0);
+        app.dispatch(Event::NewEvents(crate::event::EventLoopStartCause::Poll));
+        app.dispatch(key_event(window_id, ElementState::Pressed));
+        assert_eq!(app.event_handler().key_presses, 1);
+
+        app.event_handler_mut().error_response = Some(ErrorResponse::Continue);
+        assert_eq!(app.dispatch(file_dropped()), ControlFlow::Continue);
+        assert_eq!(app.dispatch(file_dropped()), ControlFlow::Exit);
+        assert_eq!(
+            app.event_handler().calls,
+            vec!["on_error", "on_error", "on_error", "on_shutdown"]
+        );
+
+        match app.finish() {
+            Err(ApplicationError::Exited { status, error }) => {
+                assert_eq!(status.reason(), ExitReason::EventHandlerError);
+                match *error {
+                    ApplicationError::EventHandlerFailed(e) => assert_eq!(e, MyError),
+                    _ => panic!("Unexpected application error"),
+                }
+            }
+            _ => panic!("Unexpected application result"),
+        }
+    }
+
+    #[cfg(feature = "catch-panic")]
+    #[derive(Debug, Default)]
+    struct PanicIsolation {
+        calls: Vec<&'static str>,
+    }
+
+    #[cfg(feature = "catch-panic")]
+    impl EventHandler for PanicIsolation {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_received_character(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            _c: char,
+        ) -> Result<ControlFlow, Self::Error> {
+            panic!("Received character");
+        }
+
+        fn on_panic(&mut self, callback: &'static str, _payload: &(dyn std::any::Any + Send)) {
+            self.calls.push(callback);
+        }
+
+        fn on_shutdown(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _reason: ExitReason,
+        ) -> Result<(), Self::Error> {
+            self.calls.push("on_shutdown");
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "catch-panic")]
+    fn panic_isolation() {
+        use crate::application::PanicPolicy;
+
+        let window_id = unsafe { WindowId::dummy() };
+        let character_received = || Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ReceivedCharacter('a'),
+        };
+
+        let mut app = HeadlessApplication::<PanicIsolation>::from_builder(
+            ApplicationBuilder::new().with_panic_policy(PanicPolicy::Continue),
+        );
+        assert_eq!(app.dispatch(character_received()), ControlFlow::Continue);
+        assert_eq!(app.event_handler().calls, vec!["on_received_character"]);
+
+        let mut app = HeadlessApplication::<PanicIsolation>::from_builder(
+            ApplicationBuilder::new().with_panic_policy(PanicPolicy::Exit),
+        );
+        assert_eq!(app.dispatch(character_received()), ControlFlow::Exit);
+        assert_eq!(
+            app.event_handler().calls,
+            vec!["on_received_character", "on_shutdown"]
+        );
+        match app.finish() {
+            Err(ApplicationError::Exited { status, error }) => {
+                assert_eq!(status.reason(), ExitReason::EventHandlerPanic);
+                match *error {
+                    ApplicationError::EventHandlerPanicked { callback, message } => {
+                        assert_eq!(callback, "on_received_character");
+                        assert_eq!(message, "Received character");
+                    }
+                    _ => panic!("Unexpected application error"),
+                }
+            }
+            _ => panic!("Unexpected application result"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "catch-panic")]
+    fn scheduled_work_panics() {
+        use crate::application::{ManualClock, PanicPolicy, Timer, TimerAction};
+
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<PanicIsolation>::from_builder(
+            ApplicationBuilder::new()
+                .with_panic_policy(PanicPolicy::Continue)
+                .with_clock(clock.clone()),
+        );
+        let once = app.handle().schedule(Timer::once(
+            std::time::Duration::from_millis(10),
+            TimerAction::callback(|| panic!("Timer")),
+        ));
+        let repeating = app.handle().schedule(Timer::repeating(
+            std::time::Duration::from_millis(20),
+            TimerAction::callback(|| panic!("Timer")),
+        ));
+        let task = app
+            .handle()
+            .spawn_detached(async { panic!("Task") })
+            .unwrap();
+
+        // Each panic interrupts the update, the rest of the work is done in the next ones.
+        clock.advance(std::time::Duration::from_millis(20));
+        assert_eq!(
+            app.dispatch(Event::MainEventsCleared),
+            ControlFlow::Continue
+        );
+        assert!(!app.handle().is_timer_scheduled(once));
+        assert_eq!(
+            app.dispatch(Event::MainEventsCleared),
+            ControlFlow::Continue
+        );
+        assert!(app.handle().is_timer_scheduled(repeating));
+        assert_eq!(
+            app.dispatch(Event::MainEventsCleared),
+            ControlFlow::Continue
+        );
+        assert!(!app.handle().is_task_running(task));
+
+        clock.advance(std::time::Duration::from_millis(20));
+        assert_eq!(
+            app.dispatch(Event::MainEventsCleared),
+            ControlFlow::Continue
+        );
+        assert!(app.handle().is_timer_scheduled(repeating));
+        assert_eq!(
+            app.event_handler().calls,
+            vec!["timer_callback", "timer_callback", "task", "timer_callback"]
+        );
+    }
+
+    #[test]
+    fn creation_failure() {
+        let application = ApplicationBuilder::new().build::<KeyPresses>().unwrap();
+        match HeadlessApplication::try_new(application, |_| Err(MyError)) {
+            Err(ApplicationError::EventHandlerCreationFailed(MyError)) => (),
+            _ => panic!("The creation error wasn't propagated"),
+        }
+
+        let application = ApplicationBuilder::new().build::<KeyPresses>().unwrap();
+        let app = HeadlessApplication::try_new(application, |_| Ok(KeyPresses::default())).unwrap();
+        assert_eq!(
+            app.finish().unwrap().reason(),
+            ExitReason::EventLoopDestroyed
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+};
+
+use super::LoopWaker;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct JobHandle(u64);
+
+pub struct JobContext {
+    job: JobHandle,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<Vec<(JobHandle, f32)>>>,
+    loop_waker: Arc<LoopWaker>,
+}
+
+impl JobContext {
+    pub fn job(&self) -> JobHandle {
+        self.job
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn report_progress(&self, progress: f32) {
+        lock(&self.progress).push((self.job, progress));
+        self.loop_waker.wake_up();
+    }
+}
+
+impl std::fmt::Debug for JobContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobContext")
+            .field("job", &self.job)
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+type JobFunction<CustomEvent> = Box<dyn FnOnce(&JobContext) -> CustomEvent + Send>;
+
+struct QueuedJob<CustomEvent> {
+    job: JobHandle,
+    cancelled: Arc<AtomicBool>,
+    function: JobFunction<CustomEvent>,
+}
+
+struct JobQueue<CustomEvent> {
+    jobs: VecDeque<QueuedJob<CustomEvent>>,
+    shutting_down: bool,
+}
+
+struct JobPoolState<CustomEvent> {
+    queue: Mutex<JobQueue<CustomEvent>>,
+    queue_changed: Condvar,
+    // Results are None for jobs that panicked.
+    results: Mutex<Vec<(JobHandle, Option<CustomEvent>)>>,
+    progress: Arc<Mutex<Vec<(JobHandle, f32)>>>,
+    loop_waker: Arc<LoopWaker>,
+}
+
+pub(crate) enum JobMessage<CustomEvent> {
+    Progress(JobHandle, f32),
+    Completed(JobHandle, CustomEvent),
+    Failed(JobHandle),
+}
+
+pub(crate) struct JobPool<CustomEvent> {
+    state: Arc<JobPoolState<CustomEvent>>,
+    worker_count: usize,
+    workers: Vec<std::thread::JoinHandle<()>>,
+    jobs: HashMap<JobHandle, Arc<AtomicBool>>,
+    next_job: u64,
+}
+
+impl<CustomEvent> std::fmt::Debug for JobPool<CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobPool")
+            .field("worker_count", &self.worker_count)
+            .field("jobs", &self.jobs.len())
+            .finish()
+    }
+}
+
+impl<CustomEvent> JobPool<CustomEvent> {
+    pub fn new(worker_count: usize, loop_waker: Arc<LoopWaker>) -> Self {
+        Self {
+            state: Arc::new(JobPoolState {
+                queue: Mutex::new(JobQueue {
+                    jobs: VecDeque::new(),
+                    shutting_down: false,
+                }),
+                queue_changed: Condvar::new(),
+                results: Mutex::new(Vec::new()),
+                progress: Arc::new(Mutex::new(Vec::new())),
+                loop_waker,
+            }),
+            worker_count,
+            workers: Vec::new(),
+            jobs: HashMap::new(),
+            next_job: 0,
+        }
+    }
+
+    pub fn submit(&mut self, function: JobFunction<CustomEvent>) -> JobHandle
+    where
+        CustomEvent: Send + 'static,
+    {
+        // Workers are only started once there is some work for them, which restarts the pool
+        // after a shut down.
+        if self.workers.is_empty() {
+            lock(&self.state.queue).shutting_down = false;
+            for i in 0..self.worker_count {
+                let state = Arc::clone(&self.state);
+                self.workers.push(
+                    std::thread::Builder::new()
+                        .name(format!("rae_app job worker {}", i))
+                        .spawn(move || run_worker(&state))
+                        .expect("Failed to start a job worker thread"),
+                );
+            }
+        }
+        let job = JobHandle(self.next_job);
+        self.next_job += 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.jobs.insert(job, Arc::clone(&cancelled));
+        lock(&self.state.queue).jobs.push_back(QueuedJob {
+            job,
+            cancelled,
+            function,
+        });
+        self.state.queue_changed.notify_one();
+        job
+    }
+
+    pub fn cancel(&mut self, job: JobHandle) -> bool {
+        match self.jobs.remove(&job) {
+            Some(cancelled) => {
+                cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cancel_all(&mut self) {
+        for (_, cancelled) in self.jobs.drain() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_running(&self, job: JobHandle) -> bool {
+        self.jobs.contains_key(&job)
+    }
+
+    // Messages of cancelled jobs are discarded, and a job is done once its result is taken.
+    pub fn take_messages(&mut self) -> Vec<JobMessage<CustomEvent>> {
+        let progress = std::mem::take(&mut *lock(&self.state.progress));
+        let results = std::mem::take(&mut *lock(&self.state.results));
+        let mut messages = Vec::new();
+        for (job, progress) in progress {
+            if self.jobs.contains_key(&job) {
+                messages.push(JobMessage::Progress(job, progress));
+            }
+        }
+        for (job, result) in results {
+            if self.jobs.remove(&job).is_some() {
+                messages.push(match result {
+                    Some(result) => JobMessage::Completed(job, result),
+                    None => JobMessage::Failed(job),
+                });
+            }
+        }
+        messages
+    }
+
+    pub fn shut_down(&mut self) {
+        self.cancel_all();
+        {
+            let mut queue = lock(&self.state.queue);
+            queue.shutting_down = true;
+            queue.jobs.clear();
+        }
+        self.state.queue_changed.notify_all();
+        let current_thread = std::thread::current().id();
+        for worker in self.workers.drain(..) {
+            // The pool can be dropped by a job owning the last application handle.
+            if worker.thread().id() != current_thread {
+                let _ = worker.join();
+            }
+        }
+    }
+}
+
+impl<CustomEvent> Drop for JobPool<CustomEvent> {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
+
+fn run_worker<CustomEvent>(state: &JobPoolState<CustomEvent>) {
+    loop {
+        let job = {
+            let mut queue = lock(&state.queue);
+            loop {
+                if queue.shutting_down {
+                    return;
+                }
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                queue = state
+                    .queue_changed
+                    .wait(queue)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        };
+        if job.cancelled.load(Ordering::Relaxed) {
+            continue;
+        }
+        let context = JobContext {
+            job: job.job,
+            cancelled: job.cancelled,
+            progress: Arc::clone(&state.progress),
+            loop_waker: Arc::clone(&state.loop_waker),
+        };
+        let function = job.function;
+        // A panicking job must not take its worker down with it.
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| function(&context))).ok();
+        lock(&state.results).push((job.job, result));
+        state.loop_waker.wake_up();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs() {
+        let mut pool = JobPool::new(2, Arc::new(LoopWaker::default()));
+        let canceled = pool.submit(Box::new(|context: &JobContext| {
+            while !context.is_cancelled() {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            0
+        }));
+        let job = pool.submit(Box::new(|context: &JobContext| {
+            context.report_progress(0.5);
+            1
+        }));
+        let failed = pool.submit(Box::new(|_: &JobContext| panic!("Job")));
+        assert!(pool.cancel(canceled));
+        assert!(!pool.is_running(canceled));
+
+        let mut progress = Vec::new();
+        let mut results = Vec::new();
+        let mut failures = Vec::new();
+        for _ in 0..1000 {
+            for message in pool.take_messages() {
+                match message {
+                    JobMessage::Progress(job, value) => progress.push((job, value)),
+                    JobMessage::Completed(job, result) => results.push((job, result)),
+                    JobMessage::Failed(job) => failures.push(job),
+                }
+            }
+            if !pool.is_running(job) && !pool.is_running(failed) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(!pool.is_running(job));
+        assert_eq!(progress, vec![(job, 0.5)]);
+        assert_eq!(results, vec![(job, 1)]);
+        assert_eq!(failures, vec![failed]);
+
+        pool.submit(Box::new(|context: &JobContext| {
+            while !context.is_cancelled() {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            2
+        }));
+        pool.shut_down();
+        assert!(pool.take_messages().is_empty());
+    }
+
+    #[test]
+    fn restart() {
+        let mut pool = JobPool::new(1, Arc::new(LoopWaker::default()));
+        pool.shut_down();
+        let job = pool.submit(Box::new(|_: &JobContext| 1));
+
+        let mut results = Vec::new();
+        for _ in 0..1000 {
+            for message in pool.take_messages() {
+                if let JobMessage::Completed(job, result) = message {
+                    results.push((job, result));
+                }
+            }
+            if !pool.is_running(job) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(!pool.is_running(job));
+        assert_eq!(results, vec![(job, 1)]);
+    }
+}
@@ -0,0 +1,275 @@
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct LayerId(u64);
+
+// Only the order and the state of the layers are shared, the layers themselves are owned by the
+// application.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LayerStack {
+    layers: Vec<(LayerId, bool)>,
+    next_id: u64,
+}
+
+impl LayerStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self) -> LayerId {
+        let id = LayerId(self.next_id);
+        self.next_id += 1;
+        self.layers.push((id, true));
+        id
+    }
+
+    pub fn layers(&self) -> Vec<LayerId> {
+        self.layers.iter().map(|(id, _)| *id).collect()
+    }
+
+    pub fn enabled_layers(&self) -> Vec<LayerId> {
+        self.layers
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    pub fn is_enabled(&self, id: LayerId) -> bool {
+        self.layers
+            .iter()
+            .any(|(layer_id, enabled)| *layer_id == id && *enabled)
+    }
+
+    pub fn set_enabled(&mut self, id: LayerId, enabled: bool) -> bool {
+        match self.layers.iter_mut().find(|(layer_id, _)| *layer_id == id) {
+            Some(layer) => {
+                layer.1 = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The index is clamped to the stack size.
+    pub fn move_to(&mut self, id: LayerId, index: usize) -> bool {
+        match self.layers.iter().position(|(layer_id, _)| *layer_id == id) {
+            Some(i) => {
+                let layer = self.layers.remove(i);
+                let index = std::cmp::min(index, self.layers.len());
+                self.layers.insert(index, layer);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::{key_event, MyError},
+            ApplicationBuilder, ApplicationHandle, ExitReason, HeadlessApplication,
+        },
+        event::{
+            ControlFlow, DeviceId, ElementState, Event, EventContext, EventHandler, EventLoop,
+            KeyboardInput, Layer, LayerCallback, LayerResponse, WindowEvent,
+        },
+        window::WindowId,
+    };
+
+    #[test]
+    fn ordering() {
+        let mut stack = LayerStack::new();
+        let a = stack.add();
+        let b = stack.add();
+        let c = stack.add();
+        assert_eq!(stack.layers(), vec![a, b, c]);
+
+        assert!(stack.move_to(c, 0));
+        assert!(stack.move_to(a, 10));
+        assert_eq!(stack.layers(), vec![c, b, a]);
+
+        assert!(stack.set_enabled(b, false));
+        assert!(!stack.is_enabled(b));
+        assert_eq!(stack.enabled_layers(), vec![c, a]);
+        assert!(!stack.set_enabled(LayerId(10), false));
+    }
+
+    #[derive(Debug, Default)]
+    struct Input {
+        characters: Vec<char>,
+        key_states: Vec<bool>,
+    }
+
+    impl EventHandler for Input {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_key_pressed(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            _device_id: DeviceId,
+            _scan_code: crate::event::keyboard::ScanCode,
+            _key_code: Option<crate::event::keyboard::KeyCode>,
+            _is_synthetic: bool,
+            _is_repeat: bool,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.key_states.push(true);
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_key_released(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            _device_id: DeviceId,
+            _scan_code: crate::event::keyboard::ScanCode,
+            _key_code: Option<crate::event::keyboard::KeyCode>,
+            _is_synthetic: bool,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.key_states.push(false);
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_received_character(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _wid: WindowId,
+            c: char,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.characters.push(c);
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    struct FilterLayer;
+
+    impl Layer<Input> for FilterLayer {
+        fn on_callback(
+            &mut self,
+            _context: &mut EventContext<()>,
+            callback: &LayerCallback<()>,
+        ) -> Result<LayerResponse, MyError> {
+            match callback {
+                LayerCallback::Event(Event::WindowEvent {
+                    event: WindowEvent::ReceivedCharacter('x'),
+                    ..
+                }) => Ok(LayerResponse::Consume(ControlFlow::Continue)),
+                LayerCallback::Event(Event::WindowEvent {
+                    event: WindowEvent::ReceivedCharacter('q'),
+                    ..
+                }) => Ok(LayerResponse::Consume(ControlFlow::Exit)),
+                LayerCallback::Event(Event::WindowEvent {
+                    event:
+                        WindowEvent::KeyboardInput {
+                            input:
+                                KeyboardInput {
+                                    state: ElementState::Released,
+                                    ..
+                                },
+                            ..
+                        },
+                    ..
+                }) => Ok(LayerResponse::Consume(ControlFlow::Continue)),
+                _ => Ok(LayerResponse::Pass),
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct RecorderLayer {
+        characters: std::sync::Arc<std::sync::Mutex<Vec<char>>>,
+        key_states: std::sync::Arc<std::sync::Mutex<Vec<bool>>>,
+        main_events_cleared: std::sync::Arc<std::sync::atomic::AtomicU32>,
+    }
+
+    impl Layer<Input> for RecorderLayer {
+        fn on_callback(
+            &mut self,
+            context: &mut EventContext<()>,
+            callback: &LayerCallback<()>,
+        ) -> Result<LayerResponse, MyError> {
+            match callback {
+                LayerCallback::Event(Event::WindowEvent {
+                    event: WindowEvent::ReceivedCharacter(c),
+                    ..
+                }) => self.characters.lock().unwrap().push(*c),
+                LayerCallback::Event(Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::KeyboardInput { input, .. },
+                }) => self.key_states.lock().unwrap().push(
+                    context
+                        .keyboard_state()
+                        .is_key_pressed(Some(*window_id), input.scancode),
+                ),
+                LayerCallback::MainEventsCleared => {
+                    self.main_events_cleared
+                        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+                _ => (),
+            }
+            Ok(LayerResponse::Pass)
+        }
+    }
+
+    #[test]
+    fn layers() {
+        let window_id = unsafe { WindowId::dummy() };
+        let character = |c| Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ReceivedCharacter(c),
+        };
+        let recorder = RecorderLayer::default();
+        let characters = std::sync::Arc::clone(&recorder.characters);
+        let key_states = std::sync::Arc::clone(&recorder.key_states);
+        let main_events_cleared = std::sync::Arc::clone(&recorder.main_events_cleared);
+        let mut application = ApplicationBuilder::new().build::<Input>().unwrap();
+        let filter_id = application.add_layer(FilterLayer);
+        let recorder_id = application.add_layer(recorder);
+        let mut app = HeadlessApplication::new(application, Input::default());
+        assert_eq!(app.handle().layers(), vec![filter_id, recorder_id]);
+
+        app.dispatch_all(vec![
+            character('a'),
+            character('x'),
+            Event::MainEventsCleared,
+        ]);
+        assert_eq!(*characters.lock().unwrap(), vec!['a']);
+        assert_eq!(app.event_handler().characters, vec!['a']);
+        assert_eq!(
+            main_events_cleared.load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+
+        assert!(app.handle().move_layer(recorder_id, 0));
+        assert_eq!(app.handle().layers(), vec![recorder_id, filter_id]);
+        app.dispatch(character('x'));
+        assert_eq!(*characters.lock().unwrap(), vec!['a', 'x']);
+        assert_eq!(app.event_handler().characters, vec!['a']);
+
+        // A consumed key release still updates the keyboard state.
+        app.dispatch(key_event(window_id, ElementState::Pressed));
+        app.dispatch(key_event(window_id, ElementState::Released));
+        assert_eq!(*key_states.lock().unwrap(), vec![true, false]);
+        assert_eq!(app.event_handler().key_states, vec![true]);
+
+        assert!(app.handle().set_layer_enabled(filter_id, false));
+        assert!(!app.handle().is_layer_enabled(filter_id));
+        app.dispatch(character('x'));
+        assert_eq!(app.event_handler().characters, vec!['a', 'x']);
+
+        app.handle().set_layer_enabled(filter_id, true);
+        assert_eq!(app.dispatch(character('q')), ControlFlow::Exit);
+        assert_eq!(app.event_handler().characters, vec!['a', 'x']);
+        assert_eq!(
+            app.finish().unwrap().reason(),
+            ExitReason::EventHandlerRequest
+        );
+    }
+}
@@ -0,0 +1,7 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum LoopMode {
+    #[default]
+    Poll,
+    WaitUntil,
+    Wait,
+}
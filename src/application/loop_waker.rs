@@ -0,0 +1,30 @@
+use std::sync::Mutex;
+
+#[derive(Default)]
+pub(crate) struct LoopWaker {
+    wake_up: Mutex<Option<Box<dyn Fn() + Send + Sync>>>,
+}
+
+impl LoopWaker {
+    pub fn set(&self, wake_up: Option<Box<dyn Fn() + Send + Sync>>) {
+        *self.lock() = wake_up;
+    }
+
+    pub fn wake_up(&self) {
+        if let Some(wake_up) = self.lock().as_ref() {
+            wake_up();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Box<dyn Fn() + Send + Sync>>> {
+        self.wake_up
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for LoopWaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("LoopWaker { .. }")
+    }
+}
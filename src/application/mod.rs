@@ -1,2 +1,64 @@
 mod application;
 pub use application::*;
+
+mod application_error;
+pub use application_error::*;
+
+mod exit_status;
+pub use exit_status::*;
+
+mod application_builder;
+pub use application_builder::*;
+
+mod loop_mode;
+pub use loop_mode::*;
+
+mod clock;
+pub(crate) use clock::SharedClock;
+pub use clock::{Clock, ManualClock, RealClock};
+
+mod fixed_update_channel;
+pub use fixed_update_channel::FixedUpdateChannelId;
+pub(crate) use fixed_update_channel::{FixedUpdateChannel, FixedUpdateChannelSettings};
+
+mod frame_stats;
+pub(crate) use frame_stats::FrameStatsRecorder;
+pub use frame_stats::{CallbackCategory, FrameStats};
+
+mod frame_limiter;
+pub use frame_limiter::InvalidFrequency;
+pub(crate) use frame_limiter::{frequency_period, is_valid_frequency, FrameLimiter};
+
+mod scheduler;
+pub(crate) use scheduler::Scheduler;
+pub use scheduler::{TimeBase, Timer, TimerAction, TimerHandle};
+
+mod loop_waker;
+pub(crate) use loop_waker::LoopWaker;
+
+mod executor;
+pub(crate) use executor::{poll_task, Executor};
+pub use executor::{SpawnError, TaskHandle};
+
+mod job_pool;
+pub use job_pool::{JobContext, JobHandle};
+pub(crate) use job_pool::{JobMessage, JobPool};
+
+mod layer_stack;
+pub use layer_stack::LayerId;
+pub(crate) use layer_stack::LayerStack;
+
+mod application_handle;
+pub use application_handle::*;
+
+mod simulation_clock;
+pub use simulation_clock::InvalidTimeScale;
+pub(crate) use simulation_clock::*;
+
+mod headless_application;
+#[cfg(test)]
+pub(crate) use headless_application::test_utils;
+pub use headless_application::*;
+
+mod embedded_application;
+pub use embedded_application::*;
@@ -0,0 +1,393 @@
+use std::convert::TryFrom;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TimeBase {
+    #[default]
+    Simulation,
+    Real,
+}
+
+pub enum TimerAction<CustomEvent> {
+    Callback(Box<dyn FnMut() + Send>),
+    Event(Box<dyn FnMut() -> CustomEvent + Send>),
+}
+
+impl<CustomEvent> TimerAction<CustomEvent> {
+    pub fn callback<F>(callback: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        TimerAction::Callback(Box::new(callback))
+    }
+
+    pub fn event(event: CustomEvent) -> Self
+    where
+        CustomEvent: Clone + Send + 'static,
+    {
+        TimerAction::Event(Box::new(move || event.clone()))
+    }
+}
+
+impl<CustomEvent> std::fmt::Debug for TimerAction<CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimerAction::Callback(_) => write!(f, "Callback"),
+            TimerAction::Event(_) => write!(f, "Event"),
+        }
+    }
+}
+
+pub struct Timer<CustomEvent> {
+    delay: std::time::Duration,
+    period: Option<std::time::Duration>,
+    time_base: TimeBase,
+    action: TimerAction<CustomEvent>,
+}
+
+impl<CustomEvent> Timer<CustomEvent> {
+    pub fn once(delay: std::time::Duration, action: TimerAction<CustomEvent>) -> Self {
+        Self {
+            delay,
+            period: None,
+            time_base: TimeBase::default(),
+            action,
+        }
+    }
+
+    pub fn repeating(period: std::time::Duration, action: TimerAction<CustomEvent>) -> Self {
+        Self {
+            delay: period,
+            period: Some(period),
+            time_base: TimeBase::default(),
+            action,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: std::time::Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_time_base(mut self, time_base: TimeBase) -> Self {
+        self.time_base = time_base;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct TimerHandle(u64);
+
+struct ScheduledTimer<CustomEvent> {
+    handle: TimerHandle,
+    deadline: std::time::Duration,
+    period: Option<std::time::Duration>,
+    time_base: TimeBase,
+    // The action is taken out of the scheduler while it runs.
+    action: Option<TimerAction<CustomEvent>>,
+}
+
+pub(crate) struct Scheduler<CustomEvent> {
+    timers: Vec<ScheduledTimer<CustomEvent>>,
+    next_handle: u64,
+}
+
+impl<CustomEvent> std::fmt::Debug for Scheduler<CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("timers", &self.timers.len())
+            .finish()
+    }
+}
+
+impl<CustomEvent> Scheduler<CustomEvent> {
+    pub fn new() -> Self {
+        Self {
+            timers: Vec::new(),
+            next_handle: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    pub fn schedule(
+        &mut self,
+        timer: Timer<CustomEvent>,
+        simulation_time: std::time::Duration,
+        real_time: std::time::Duration,
+    ) -> TimerHandle {
+        let handle = TimerHandle(self.next_handle);
+        self.next_handle += 1;
+        let current_time = match timer.time_base {
+            TimeBase::Simulation => simulation_time,
+            TimeBase::Real => real_time,
+        };
+        self.timers.push(ScheduledTimer {
+            handle,
+            deadline: current_time.saturating_add(timer.delay),
+            period: timer.period,
+            time_base: timer.time_base,
+            action: Some(timer.action),
+        });
+        handle
+    }
+
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        let count = self.timers.len();
+        self.timers.retain(|timer| timer.handle != handle);
+        self.timers.len() != count
+    }
+
+    pub fn is_scheduled(&self, handle: TimerHandle) -> bool {
+        self.timers.iter().any(|timer| timer.handle == handle)
+    }
+
+    pub fn next_deadline(&self, time_base: TimeBase) -> Option<std::time::Duration> {
+        self.timers
+            .iter()
+            .filter(|timer| timer.time_base == time_base && timer.action.is_some())
+            .map(|timer| timer.deadline)
+            .min()
+    }
+
+    pub fn take_due(
+        &mut self,
+        simulation_time: std::time::Duration,
+        real_time: std::time::Duration,
+    ) -> Vec<(TimerHandle, TimerAction<CustomEvent>)> {
+        let mut due: Vec<_> = self
+            .timers
+            .iter_mut()
+            .filter(|timer| {
+                let current_time = match timer.time_base {
+                    TimeBase::Simulation => simulation_time,
+                    TimeBase::Real => real_time,
+                };
+                timer.deadline <= current_time
+            })
+            .filter_map(|timer| {
+                timer
+                    .action
+                    .take()
+                    .map(|action| (timer.deadline, timer.handle, action))
+            })
+            .collect();
+        due.sort_by_key(|(deadline, handle, _)| (*deadline, *handle));
+        due.into_iter()
+            .map(|(_, handle, action)| (handle, action))
+            .collect()
+    }
+
+    pub fn finish(
+        &mut self,
+        handle: TimerHandle,
+        action: TimerAction<CustomEvent>,
+        fired: bool,
+        simulation_time: std::time::Duration,
+        real_time: std::time::Duration,
+    ) {
+        let i = match self.timers.iter().position(|timer| timer.handle == handle) {
+            Some(i) => i,
+            None => return,
+        };
+        if !fired {
+            self.timers[i].action = Some(action);
+            return;
+        }
+        let timer = &mut self.timers[i];
+        match timer.period {
+            Some(period) => {
+                let current_time = match timer.time_base {
+                    TimeBase::Simulation => simulation_time,
+                    TimeBase::Real => real_time,
+                };
+                // A repeating timer fires at most once per update, skipping the missed periods.
+                timer.deadline = if period.as_nanos() == 0 {
+                    current_time
+                } else {
+                    let period = period.as_nanos();
+                    let skipped_time =
+                        ((current_time - timer.deadline).as_nanos() / period + 1) * period;
+                    u64::try_from(skipped_time)
+                        .ok()
+                        .and_then(|t| {
+                            timer
+                                .deadline
+                                .checked_add(std::time::Duration::from_nanos(t))
+                        })
+                        .unwrap_or(std::time::Duration::MAX)
+                };
+                timer.action = Some(action);
+            }
+            None => {
+                self.timers.remove(i);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, HeadlessApplication,
+            ManualClock,
+        },
+        event::{ControlFlow, Event, EventContext, EventHandler, EventLoop},
+    };
+
+    fn fire(
+        scheduler: &mut Scheduler<u32>,
+        simulation_time: std::time::Duration,
+        real_time: std::time::Duration,
+    ) -> Vec<u32> {
+        let mut events = Vec::new();
+        for (timer, mut action) in scheduler.take_due(simulation_time, real_time) {
+            if let TimerAction::Event(event) = &mut action {
+                events.push(event());
+            }
+            scheduler.finish(timer, action, true, simulation_time, real_time);
+        }
+        events
+    }
+
+    #[test]
+    fn timers() {
+        let ms = std::time::Duration::from_millis;
+        let mut scheduler = Scheduler::new();
+        let once = scheduler.schedule(Timer::once(ms(25), TimerAction::event(1)), ms(0), ms(0));
+        let repeating = scheduler.schedule(
+            Timer::repeating(ms(10), TimerAction::event(2)).with_time_base(TimeBase::Real),
+            ms(0),
+            ms(0),
+        );
+        let canceled = scheduler.schedule(Timer::once(ms(5), TimerAction::event(3)), ms(0), ms(0));
+        assert!(scheduler.cancel(canceled));
+        assert!(!scheduler.is_scheduled(canceled));
+        assert_eq!(scheduler.next_deadline(TimeBase::Simulation), Some(ms(25)));
+        assert_eq!(scheduler.next_deadline(TimeBase::Real), Some(ms(10)));
+
+        // A repeating timer fires once per update, and its missed periods are skipped.
+        assert_eq!(fire(&mut scheduler, ms(0), ms(35)), vec![2]);
+        assert_eq!(scheduler.next_deadline(TimeBase::Real), Some(ms(40)));
+
+        assert_eq!(fire(&mut scheduler, ms(30), ms(65)), vec![1, 2]);
+        assert!(!scheduler.is_scheduled(once));
+        assert!(scheduler.is_scheduled(repeating));
+        assert_eq!(scheduler.next_deadline(TimeBase::Simulation), None);
+
+        // Timers that didn't fire keep their deadline.
+        let due = scheduler.take_due(ms(30), ms(70));
+        assert_eq!(scheduler.next_deadline(TimeBase::Real), None);
+        for (timer, action) in due {
+            scheduler.finish(timer, action, false, ms(30), ms(70));
+        }
+        assert_eq!(scheduler.next_deadline(TimeBase::Real), Some(ms(70)));
+    }
+
+    #[test]
+    fn long_stall() {
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule(
+            Timer::repeating(std::time::Duration::from_micros(1), TimerAction::event(1)),
+            std::time::Duration::from_secs(0),
+            std::time::Duration::from_secs(0),
+        );
+        // More missed periods than a u32 can hold.
+        let time = std::time::Duration::from_secs(10_000);
+        assert_eq!(fire(&mut scheduler, time, time), vec![1]);
+        assert_eq!(
+            scheduler.next_deadline(TimeBase::Simulation),
+            Some(time + std::time::Duration::from_micros(1))
+        );
+    }
+
+    #[test]
+    fn long_delay() {
+        let mut scheduler = Scheduler::new();
+        let time = std::time::Duration::from_secs(10);
+        scheduler.schedule(
+            Timer::once(std::time::Duration::MAX, TimerAction::event(1)),
+            time,
+            time,
+        );
+        assert_eq!(
+            scheduler.next_deadline(TimeBase::Simulation),
+            Some(std::time::Duration::MAX)
+        );
+        assert!(fire(&mut scheduler, time, time).is_empty());
+    }
+
+    #[derive(Debug, Default)]
+    struct CustomEvents {
+        custom_events: u32,
+    }
+
+    impl EventHandler for CustomEvents {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_custom_event(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _event: (),
+        ) -> Result<ControlFlow, Self::Error> {
+            self.custom_events += 1;
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn application_timers() {
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<CustomEvents>::from_builder(
+            ApplicationBuilder::new().with_clock(clock.clone()),
+        );
+        let callbacks = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let callbacks_clone = callbacks.clone();
+        let once = app.handle().schedule(Timer::once(
+            std::time::Duration::from_millis(25),
+            TimerAction::event(()),
+        ));
+        let repeating = app.handle().schedule(
+            Timer::repeating(
+                std::time::Duration::from_millis(10),
+                TimerAction::callback(move || {
+                    callbacks_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }),
+            )
+            .with_time_base(TimeBase::Real),
+        );
+        let canceled = app.handle().schedule(Timer::once(
+            std::time::Duration::from_millis(5),
+            TimerAction::event(()),
+        ));
+        assert!(app.handle().cancel_timer(canceled));
+        assert!(!app.handle().is_timer_scheduled(canceled));
+
+        app.handle().pause();
+        clock.advance(std::time::Duration::from_millis(30));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().custom_events, 0);
+        assert_eq!(callbacks.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(app.handle().is_timer_scheduled(once));
+
+        app.handle().resume();
+        clock.advance(std::time::Duration::from_millis(30));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().custom_events, 1);
+        assert_eq!(callbacks.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(!app.handle().is_timer_scheduled(once));
+
+        assert!(app.handle().cancel_timer(repeating));
+        clock.advance(std::time::Duration::from_millis(30));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(callbacks.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,209 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InvalidTimeScale(pub f64);
+
+impl std::fmt::Display for InvalidTimeScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid time scale {} (must be finite and not negative)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidTimeScale {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+pub(crate) fn is_valid_time_scale(time_scale: f64) -> bool {
+    time_scale.is_finite() && time_scale >= 0.
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SimulationClock {
+    time: std::time::Duration,
+    real_time: std::time::Duration,
+    last_real_time: std::time::Instant,
+    time_scale: f64,
+    paused: bool,
+    pending_fixed_steps: u32,
+}
+
+impl SimulationClock {
+    pub fn new(current_time: std::time::Instant, time_scale: f64, paused: bool) -> Self {
+        Self {
+            time: std::time::Duration::from_secs(0),
+            real_time: std::time::Duration::from_secs(0),
+            last_real_time: current_time,
+            time_scale,
+            paused,
+            pending_fixed_steps: 0,
+        }
+    }
+
+    pub fn time(&self) -> std::time::Duration {
+        self.time
+    }
+
+    pub fn real_time(&self) -> std::time::Duration {
+        self.real_time
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64) -> Result<(), InvalidTimeScale> {
+        if !is_valid_time_scale(time_scale) {
+            return Err(InvalidTimeScale(time_scale));
+        }
+        self.time_scale = time_scale;
+        Ok(())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn request_fixed_steps(&mut self, steps: u32) {
+        self.pending_fixed_steps = self.pending_fixed_steps.saturating_add(steps);
+    }
+
+    pub fn take_pending_fixed_steps(&mut self) -> u32 {
+        std::mem::replace(&mut self.pending_fixed_steps, 0)
+    }
+
+    pub fn time_until(&self, time: std::time::Duration) -> Option<std::time::Instant> {
+        if self.pending_fixed_steps > 0 {
+            Some(self.last_real_time)
+        } else if self.paused || self.time_scale == 0. {
+            None
+        } else if time <= self.time {
+            Some(self.last_real_time)
+        } else {
+            // Deadlines too far away to be represented are treated as never reached.
+            std::time::Duration::try_from_secs_f64(
+                (time - self.time).as_secs_f64() / self.time_scale,
+            )
+            .ok()
+            .and_then(|real_dt| self.last_real_time.checked_add(real_dt))
+        }
+    }
+
+    pub fn real_time_until(&self, real_time: std::time::Duration) -> std::time::Instant {
+        self.last_real_time + real_time.saturating_sub(self.real_time)
+    }
+
+    pub fn reset(&mut self, current_time: std::time::Instant) {
+        self.time = std::time::Duration::from_secs(0);
+        self.real_time = std::time::Duration::from_secs(0);
+        self.last_real_time = current_time;
+    }
+
+    pub fn advance(&mut self, current_time: std::time::Instant) -> std::time::Duration {
+        let real_dt = current_time - self.last_real_time;
+        self.last_real_time = current_time;
+        self.real_time += real_dt;
+        let dt = if self.paused {
+            std::time::Duration::from_secs(0)
+        } else {
+            std::time::Duration::try_from_secs_f64(real_dt.as_secs_f64() * self.time_scale)
+                .unwrap_or(std::time::Duration::MAX)
+        };
+        self.time = self.time.saturating_add(dt);
+        dt
+    }
+
+    pub fn skip(&mut self, dt: std::time::Duration) {
+        self.time = self.time.saturating_add(dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            test_utils::MyError, ApplicationBuilder, ApplicationHandle, FixedUpdateChannelId,
+            HeadlessApplication, ManualClock,
+        },
+        event::{ControlFlow, Event, EventContext, EventHandler, EventLoop},
+    };
+
+    #[test]
+    fn large_time_scale() {
+        let start = std::time::Instant::now();
+        let mut clock = SimulationClock::new(start, 1e30, false);
+        assert_eq!(
+            clock.advance(start + std::time::Duration::from_secs(1)),
+            std::time::Duration::MAX
+        );
+        assert_eq!(clock.time(), std::time::Duration::MAX);
+        clock.advance(start + std::time::Duration::from_secs(2));
+        assert_eq!(clock.time(), std::time::Duration::MAX);
+        assert_eq!(clock.real_time(), std::time::Duration::from_secs(2));
+    }
+
+    #[derive(Debug, Default)]
+    struct FixedUpdateCount {
+        fixed_updates: u32,
+    }
+
+    impl EventHandler for FixedUpdateCount {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_fixed_update(
+            &mut self,
+            _context: &mut EventContext<Self::CustomEvent>,
+            _channel: FixedUpdateChannelId,
+            _dt: std::time::Duration,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.fixed_updates += 1;
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn simulation_clock_controls() {
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<FixedUpdateCount>::from_builder(
+            ApplicationBuilder::new()
+                .with_fixed_update_frequency(100)
+                .with_start_paused(true)
+                .with_clock(clock.clone()),
+        );
+        clock.advance(std::time::Duration::from_millis(30));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().fixed_updates, 0);
+        assert_eq!(
+            app.handle().real_time(),
+            std::time::Duration::from_millis(30)
+        );
+
+        app.handle().step_fixed_updates(3);
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().fixed_updates, 3);
+        assert_eq!(
+            app.handle().simulation_time(),
+            std::time::Duration::from_millis(30)
+        );
+
+        assert!(app.handle().set_time_scale(-1.).is_err());
+        app.handle().set_time_scale(0.5).unwrap();
+        app.handle().resume();
+        clock.advance(std::time::Duration::from_millis(40));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().fixed_updates, 5);
+    }
+}
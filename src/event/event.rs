@@ -5,5 +5,122 @@ pub use winit::{
         DeviceEvent, DeviceId, ElementState, Event, KeyboardInput, MouseScrollDelta as ScrollDelta,
         StartCause as EventLoopStartCause, WindowEvent,
     },
-    event_loop::{EventLoop, EventLoopClosed, EventLoopProxy, EventLoopWindowTarget},
+    event_loop::EventLoopClosed,
 };
+
+// Custom events wait in a queue shared by the proxies, and the event loop only receives a
+// notification for each of them. This way, the loop can be woken up from any thread, even when
+// the custom events can't be sent to other threads.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UserEvent<CustomEvent> {
+    kind: UserEventKind,
+    p: std::marker::PhantomData<fn() -> CustomEvent>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum UserEventKind {
+    Custom,
+    Wake,
+}
+
+impl<CustomEvent> UserEvent<CustomEvent> {
+    fn new(kind: UserEventKind) -> Self {
+        Self {
+            kind,
+            p: std::marker::PhantomData,
+        }
+    }
+}
+
+pub type EventLoop<CustomEvent> = winit::event_loop::EventLoop<UserEvent<CustomEvent>>;
+
+pub type EventLoopWindowTarget<CustomEvent> =
+    winit::event_loop::EventLoopWindowTarget<UserEvent<CustomEvent>>;
+
+pub struct EventLoopProxy<CustomEvent: 'static> {
+    proxy: winit::event_loop::EventLoopProxy<UserEvent<CustomEvent>>,
+    custom_events: std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<CustomEvent>>>,
+}
+
+impl<CustomEvent: 'static> EventLoopProxy<CustomEvent> {
+    pub(crate) fn new(event_loop: &EventLoop<CustomEvent>) -> Self {
+        Self {
+            proxy: event_loop.create_proxy(),
+            custom_events: std::sync::Arc::new(std::sync::Mutex::new(
+                std::collections::VecDeque::new(),
+            )),
+        }
+    }
+
+    pub fn send_event(&self, event: CustomEvent) -> Result<(), EventLoopClosed<CustomEvent>> {
+        // The queue stays locked until the notification is sent, so that the notifications
+        // follow the queue order.
+        let mut custom_events = self.lock();
+        custom_events.push_back(event);
+        match self.proxy.send_event(UserEvent::new(UserEventKind::Custom)) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(EventLoopClosed(custom_events.pop_back().unwrap())),
+        }
+    }
+
+    pub fn wake_up(&self) -> Result<(), EventLoopClosed<()>> {
+        wake_up(&self.proxy)
+    }
+
+    // The returned function doesn't hold any custom event, so it can always be shared with
+    // other threads.
+    pub(crate) fn waker(&self) -> impl Fn() + Send + Sync + 'static {
+        let proxy = std::sync::Mutex::new(self.proxy.clone());
+        move || {
+            if let Ok(proxy) = proxy.lock() {
+                // The loop being closed means that there is nothing left to wake up.
+                let _ = wake_up(&proxy);
+            }
+        }
+    }
+
+    // Wake-up events only exist to make the event loop run another iteration, so they are not
+    // dispatched.
+    pub(crate) fn unwrap_user_event<'a>(
+        &self,
+        event: Event<'a, UserEvent<CustomEvent>>,
+    ) -> Option<Event<'a, CustomEvent>> {
+        match event {
+            Event::UserEvent(UserEvent {
+                kind: UserEventKind::Custom,
+                ..
+            }) => self.lock().pop_front().map(Event::UserEvent),
+            Event::UserEvent(_) => None,
+            event => event.map_nonuser_event().ok(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, std::collections::VecDeque<CustomEvent>> {
+        self.custom_events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn wake_up<CustomEvent>(
+    proxy: &winit::event_loop::EventLoopProxy<UserEvent<CustomEvent>>,
+) -> Result<(), EventLoopClosed<()>> {
+    proxy
+        .send_event(UserEvent::new(UserEventKind::Wake))
+        .map_err(|_| EventLoopClosed(()))
+}
+
+impl<CustomEvent: 'static> Clone for EventLoopProxy<CustomEvent> {
+    fn clone(&self) -> Self {
+        Self {
+            proxy: self.proxy.clone(),
+            custom_events: std::sync::Arc::clone(&self.custom_events),
+        }
+    }
+}
+
+impl<CustomEvent: 'static> std::fmt::Debug for EventLoopProxy<CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("EventLoopProxy { .. }")
+    }
+}
@@ -0,0 +1,221 @@
+use super::{keyboard::KeyboardState, EventLoopProxy, EventLoopWindowTarget};
+use crate::application::{ApplicationHandle, LoopMode, SharedClock};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub(crate) struct LoopRequests {
+    pub exit: bool,
+    pub restart: bool,
+}
+
+pub struct EventContext<'a, CustomEvent: 'static> {
+    window_target: Option<&'a EventLoopWindowTarget<CustomEvent>>,
+    proxy: Option<&'a EventLoopProxy<CustomEvent>>,
+    application: &'a ApplicationHandle<CustomEvent>,
+    clock: &'a SharedClock,
+    frame_count: u64,
+    fixed_update_count: u64,
+    keyboard_state: &'a KeyboardState,
+    loop_mode: &'a mut LoopMode,
+    loop_requests: &'a mut LoopRequests,
+}
+
+impl<'a, CustomEvent: 'static> EventContext<'a, CustomEvent> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        window_target: Option<&'a EventLoopWindowTarget<CustomEvent>>,
+        proxy: Option<&'a EventLoopProxy<CustomEvent>>,
+        application: &'a ApplicationHandle<CustomEvent>,
+        clock: &'a SharedClock,
+        frame_count: u64,
+        fixed_update_count: u64,
+        keyboard_state: &'a KeyboardState,
+        loop_mode: &'a mut LoopMode,
+        loop_requests: &'a mut LoopRequests,
+    ) -> Self {
+        Self {
+            window_target,
+            proxy,
+            application,
+            clock,
+            frame_count,
+            fixed_update_count,
+            keyboard_state,
+            loop_mode,
+            loop_requests,
+        }
+    }
+
+    // Headless applications have no event loop, so there is no window target or proxy.
+    pub fn window_target(&self) -> Option<&'a EventLoopWindowTarget<CustomEvent>> {
+        self.window_target
+    }
+
+    pub fn proxy(&self) -> Option<&'a EventLoopProxy<CustomEvent>> {
+        self.proxy
+    }
+
+    pub fn application(&self) -> &'a ApplicationHandle<CustomEvent> {
+        self.application
+    }
+
+    pub fn now(&self) -> std::time::Instant {
+        self.clock.now()
+    }
+
+    pub fn simulation_time(&self) -> std::time::Duration {
+        self.application.simulation_time()
+    }
+
+    pub fn real_time(&self) -> std::time::Duration {
+        self.application.real_time()
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn fixed_update_count(&self) -> u64 {
+        self.fixed_update_count
+    }
+
+    pub fn keyboard_state(&self) -> &'a KeyboardState {
+        self.keyboard_state
+    }
+
+    pub fn loop_mode(&self) -> LoopMode {
+        *self.loop_mode
+    }
+
+    pub fn set_loop_mode(&mut self, loop_mode: LoopMode) {
+        *self.loop_mode = loop_mode;
+    }
+
+    // Requests are handled once the event being dispatched has been fully processed.
+    pub fn request_exit(&mut self) {
+        self.loop_requests.exit = true;
+    }
+
+    pub fn request_restart(&mut self) {
+        self.loop_requests.restart = true;
+    }
+}
+
+impl<'a, CustomEvent: 'static> std::fmt::Debug for EventContext<'a, CustomEvent> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventContext")
+            .field("has_window_target", &self.window_target.is_some())
+            .field("frame_count", &self.frame_count)
+            .field("fixed_update_count", &self.fixed_update_count)
+            .field("loop_mode", &self.loop_mode)
+            .field("loop_requests", &self.loop_requests)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        application::{
+            test_utils::{key_event, MyError},
+            ApplicationBuilder, ApplicationHandle, ExitReason, HeadlessApplication, LoopMode,
+            ManualClock,
+        },
+        event::{
+            ControlFlow, DeviceId, ElementState, Event, EventContext, EventHandler, EventLoop,
+        },
+        window::WindowId,
+    };
+
+    #[derive(Debug, Default)]
+    struct ContextEventHandler {
+        key_states: Vec<bool>,
+        frame_counts: Vec<(u64, u64)>,
+    }
+
+    impl EventHandler for ContextEventHandler {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_key_pressed(
+            &mut self,
+            context: &mut EventContext<()>,
+            wid: WindowId,
+            _device_id: DeviceId,
+            scan_code: crate::event::keyboard::ScanCode,
+            _key_code: Option<crate::event::keyboard::KeyCode>,
+            _is_synthetic: bool,
+            _is_repeat: bool,
+        ) -> Result<ControlFlow, Self::Error> {
+            assert!(context.window_target().is_none());
+            assert!(context.proxy().is_none());
+            self.key_states.push(
+                context
+                    .keyboard_state()
+                    .is_key_pressed(Some(wid), scan_code),
+            );
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_key_released(
+            &mut self,
+            context: &mut EventContext<()>,
+            wid: WindowId,
+            _device_id: DeviceId,
+            scan_code: crate::event::keyboard::ScanCode,
+            _key_code: Option<crate::event::keyboard::KeyCode>,
+            _is_synthetic: bool,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.key_states.push(
+                context
+                    .keyboard_state()
+                    .is_key_pressed(Some(wid), scan_code),
+            );
+            context.request_exit();
+            Ok(ControlFlow::Continue)
+        }
+
+        fn on_main_events_cleared(
+            &mut self,
+            context: &mut EventContext<()>,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.frame_counts
+                .push((context.frame_count(), context.fixed_update_count()));
+            context.set_loop_mode(LoopMode::Wait);
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn event_context() {
+        let window_id = unsafe { WindowId::dummy() };
+        let clock = ManualClock::new();
+        let mut app = HeadlessApplication::<ContextEventHandler>::from_builder(
+            ApplicationBuilder::new()
+                .with_clock(clock.clone())
+                .with_fixed_update_frequency(100),
+        );
+
+        app.dispatch(Event::MainEventsCleared);
+        clock.advance(std::time::Duration::from_millis(20));
+        app.dispatch(Event::MainEventsCleared);
+        assert_eq!(app.event_handler().frame_counts, vec![(1, 0), (2, 2)]);
+
+        assert_eq!(
+            app.dispatch(key_event(window_id, ElementState::Pressed)),
+            ControlFlow::Continue
+        );
+        assert_eq!(
+            app.dispatch(key_event(window_id, ElementState::Released)),
+            ControlFlow::Exit
+        );
+        assert_eq!(app.event_handler().key_states, vec![true, false]);
+        assert_eq!(
+            app.finish().unwrap().reason(),
+            ExitReason::EventHandlerRequest
+        );
+    }
+}
@@ -1,9 +1,12 @@
 use super::{
     controller,
     event::{EventLoopStartCause, ScrollDelta},
-    keyboard, mouse, touch, DeviceId, EventLoop,
+    keyboard, mouse, touch, DeviceId, Event, EventContext, EventLoop,
+};
+use crate::{
+    application::{ApplicationHandle, ExitReason, FixedUpdateChannelId, JobHandle},
+    window::{PhysicalPosition, PhysicalSize, Theme, WindowId},
 };
-use crate::window::{PhysicalPosition, PhysicalSize, WindowId};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ControlFlow {
@@ -11,35 +14,86 @@ pub enum ControlFlow {
     Exit,
 }
 
-pub trait EventHandler<ErrorType, CustomEventType>
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorResponse {
+    Continue,
+    SkipFrame,
+    Restart,
+    Exit,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExitResponse {
+    Accept,
+    Veto,
+    Defer,
+}
+
+// Events passed on by the raw event hook go through the typed dispatch. Consumed events are not
+// dispatched, and the control flow is handled as if returned by a typed callback.
+#[derive(Debug, PartialEq)]
+pub enum RawEventResponse<'a, CustomEvent: 'static> {
+    Dispatch(Event<'a, CustomEvent>),
+    Consume(ControlFlow),
+}
+
+pub trait EventHandler
 where
     Self: std::marker::Sized,
-    ErrorType: std::fmt::Display + std::error::Error + 'static,
-    CustomEventType: 'static,
 {
     type Error: std::fmt::Display + std::error::Error + 'static;
     type CustomEvent: 'static;
 
-    fn new(event_loop: &EventLoop<Self::CustomEvent>) -> Result<Self, Self::Error>;
+    // The proxies created from the event loop only wake it up. Custom events are sent through
+    // the proxy of the application handle instead.
+    fn new(
+        event_loop: &EventLoop<Self::CustomEvent>,
+        application: &ApplicationHandle<Self::CustomEvent>,
+    ) -> Result<Self, Self::Error>;
+
+    fn on_raw_event<'a>(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        event: Event<'a, Self::CustomEvent>,
+    ) -> Result<RawEventResponse<'a, Self::CustomEvent>, Self::Error> {
+        Ok(RawEventResponse::Dispatch(event))
+    }
 
-    fn on_close_requested(&mut self, _wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_close_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Exit)
     }
 
-    fn on_destroyed(&mut self, _wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_destroyed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Exit)
     }
 
-    fn on_focus_gained(&mut self, _wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_focus_gained(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_focus_lost(&mut self, _wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_focus_lost(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
     fn on_resized(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _size: PhysicalSize<u32>,
     ) -> Result<ControlFlow, Self::Error> {
@@ -48,6 +102,7 @@ where
 
     fn on_scale_factor_changed<'a>(
         &self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _scale_factor: f64,
         _new_inner_size: &'a mut PhysicalSize<u32>,
@@ -57,6 +112,7 @@ where
 
     fn on_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _position: PhysicalPosition<i32>,
     ) -> Result<ControlFlow, Self::Error> {
@@ -65,6 +121,7 @@ where
 
     fn on_received_character(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _c: char,
     ) -> Result<ControlFlow, Self::Error> {
@@ -73,6 +130,7 @@ where
 
     fn on_hovered_file_dropped(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _path: std::path::PathBuf,
     ) -> Result<ControlFlow, Self::Error> {
@@ -81,18 +139,25 @@ where
 
     fn on_hovered_file_entered(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _path: std::path::PathBuf,
     ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_hovered_file_left(&mut self, _wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_hovered_file_left(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn on_key_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _scan_code: keyboard::ScanCode,
@@ -105,6 +170,7 @@ where
 
     fn on_key_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _scan_code: keyboard::ScanCode,
@@ -116,6 +182,7 @@ where
 
     fn on_modifiers_changed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _modifiers_state: keyboard::ModifiersState,
     ) -> Result<ControlFlow, Self::Error> {
@@ -124,6 +191,7 @@ where
 
     fn on_cursor_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _position: PhysicalPosition<f64>,
@@ -133,6 +201,7 @@ where
 
     fn on_cursor_entered(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -141,6 +210,7 @@ where
 
     fn on_cursor_left(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -149,6 +219,7 @@ where
 
     fn on_mouse_button_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _button: mouse::Button,
@@ -158,6 +229,7 @@ where
 
     fn on_mouse_button_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _button: mouse::Button,
@@ -167,6 +239,7 @@ where
 
     fn on_scroll(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _delta: ScrollDelta,
@@ -175,8 +248,10 @@ where
         Ok(ControlFlow::Continue)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn on_touch(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _phase: touch::TouchPhase,
@@ -189,6 +264,7 @@ where
 
     fn on_axis_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _wid: WindowId,
         _device_id: DeviceId,
         _axis: controller::AxisId,
@@ -197,16 +273,47 @@ where
         Ok(ControlFlow::Continue)
     }
 
-    fn on_device_added(&mut self, _device_id: DeviceId) -> Result<ControlFlow, Self::Error> {
+    // Only reported on some platforms, see ApplicationHandle::window_theme for the fallback.
+    fn on_theme_changed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+        _theme: Theme,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_device_removed(&mut self, _device_id: DeviceId) -> Result<ControlFlow, Self::Error> {
+    // Only reported on macOS with force touch trackpads.
+    fn on_touchpad_pressure(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+        _device_id: DeviceId,
+        _pressure: f32,
+        _stage: i64,
+    ) -> Result<ControlFlow, Self::Error> {
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_device_added(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _device_id: DeviceId,
+    ) -> Result<ControlFlow, Self::Error> {
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_device_removed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _device_id: DeviceId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
     fn on_device_cursor_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _position_delta: PhysicalPosition<f64>,
     ) -> Result<ControlFlow, Self::Error> {
@@ -215,6 +322,7 @@ where
 
     fn on_device_scroll(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _scroll_delta: ScrollDelta,
     ) -> Result<ControlFlow, Self::Error> {
@@ -223,6 +331,7 @@ where
 
     fn on_device_axis_moved(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _axis: controller::AxisId,
         _value: f64,
@@ -232,6 +341,7 @@ where
 
     fn on_device_button_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _button: controller::ButtonId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -240,6 +350,7 @@ where
 
     fn on_device_button_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _button: controller::ButtonId,
     ) -> Result<ControlFlow, Self::Error> {
@@ -248,6 +359,7 @@ where
 
     fn on_device_key_pressed(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _scan_code: keyboard::ScanCode,
         _key_code: Option<keyboard::KeyCode>,
@@ -258,6 +370,7 @@ where
 
     fn on_device_key_released(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _scan_code: keyboard::ScanCode,
         _key_code: Option<keyboard::KeyCode>,
@@ -267,56 +380,276 @@ where
 
     fn on_device_text(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _device_id: DeviceId,
         _codepoint: char,
     ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_custom_event(&mut self, _event: Self::CustomEvent) -> Result<ControlFlow, Self::Error> {
+    fn on_custom_event(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _event: Self::CustomEvent,
+    ) -> Result<ControlFlow, Self::Error> {
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_job_progress(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _job: JobHandle,
+        _progress: f32,
+    ) -> Result<ControlFlow, Self::Error> {
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_job_completed(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        _job: JobHandle,
+        event: Self::CustomEvent,
+    ) -> Result<ControlFlow, Self::Error> {
+        self.on_custom_event(context, event)
+    }
+
+    // The job panicked, so there is no result.
+    fn on_job_failed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _job: JobHandle,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
     fn on_new_events(
         &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
         _start_cause: EventLoopStartCause,
     ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_main_events_cleared(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_main_events_cleared(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_redraw_requested(&mut self, _wid: WindowId) -> Result<ControlFlow, Self::Error> {
+    fn on_redraw_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_redraw_events_cleared(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_redraw_events_cleared(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_suspended(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_suspended(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_resumed(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_resumed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_event_loop_destroyed(&mut self) -> Result<ControlFlow, Self::Error> {
+    fn on_event_loop_destroyed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Exit)
     }
 
-    fn on_fixed_update(&mut self, _dt: std::time::Duration) -> Result<ControlFlow, Self::Error> {
+    fn on_fixed_update(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _channel: FixedUpdateChannelId,
+        _dt: std::time::Duration,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_variable_update(&mut self, _dt: std::time::Duration) -> Result<ControlFlow, Self::Error> {
+    fn on_fixed_updates_dropped(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _channel: FixedUpdateChannelId,
+        _dropped_time: std::time::Duration,
+    ) -> Result<ControlFlow, Self::Error> {
         Ok(ControlFlow::Continue)
     }
 
-    fn on_error(&mut self, error: Self::Error) {
+    fn on_frame_deadline_missed(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _lateness: std::time::Duration,
+    ) -> Result<ControlFlow, Self::Error> {
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_variable_update(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _dt: std::time::Duration,
+        _fixed_update_alpha: f64,
+    ) -> Result<ControlFlow, Self::Error> {
+        Ok(ControlFlow::Continue)
+    }
+
+    fn on_exit_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _reason: ExitReason,
+    ) -> Result<ExitResponse, Self::Error> {
+        Ok(ExitResponse::Accept)
+    }
+
+    fn on_shutdown(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _reason: ExitReason,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn on_panic(&mut self, callback: &'static str, _payload: &(dyn std::any::Any + Send)) {
+        eprintln!("The event handler panicked in '{}'", callback);
+    }
+
+    fn on_error(&mut self, error: &Self::Error) -> ErrorResponse {
         eprintln!("The application shut down due to an error ({})", error);
+        ErrorResponse::Exit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        application::{
+            test_utils::{key_event, MyError},
+            ApplicationBuilder, ApplicationHandle, ExitReason, HeadlessApplication,
+        },
+        event::{
+            ControlFlow, ElementState, Event, EventContext, EventHandler, EventLoop,
+            RawEventResponse, WindowEvent,
+        },
+        window::WindowId,
+    };
+
+    #[derive(Debug, Default)]
+    struct RawEventHandler {
+        raw_events: u32,
+        characters: Vec<char>,
+        key_states: Vec<bool>,
+    }
+
+    impl EventHandler for RawEventHandler {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self::default())
+        }
+
+        fn on_raw_event<'a>(
+            &mut self,
+            context: &mut EventContext<()>,
+            event: Event<'a, ()>,
+        ) -> Result<RawEventResponse<'a, ()>, Self::Error> {
+            self.raw_events += 1;
+            match event {
+                Event::WindowEvent {
+                    event: WindowEvent::CloseRequested,
+                    ..
+                } => Ok(RawEventResponse::Consume(ControlFlow::Continue)),
+                Event::WindowEvent {
+                    event: WindowEvent::Destroyed,
+                    ..
+                } => Ok(RawEventResponse::Consume(ControlFlow::Exit)),
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::KeyboardInput { input, .. },
+                } => {
+                    self.key_states.push(
+                        context
+                            .keyboard_state()
+                            .is_key_pressed(Some(window_id), input.scancode),
+                    );
+                    Ok(RawEventResponse::Consume(ControlFlow::Continue))
+                }
+                Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::ReceivedCharacter(c),
+                } => Ok(RawEventResponse::Dispatch(Event::WindowEvent {
+                    window_id,
+                    event: WindowEvent::ReceivedCharacter(c.to_ascii_uppercase()),
+                })),
+                event => Ok(RawEventResponse::Dispatch(event)),
+            }
+        }
+
+        fn on_received_character(
+            &mut self,
+            _context: &mut EventContext<()>,
+            _wid: WindowId,
+            c: char,
+        ) -> Result<ControlFlow, Self::Error> {
+            self.characters.push(c);
+            Ok(ControlFlow::Continue)
+        }
+    }
+
+    #[test]
+    fn raw_event_hook() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut app =
+            HeadlessApplication::<RawEventHandler>::from_builder(ApplicationBuilder::new());
+        let flow = app.dispatch_all(vec![
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::ReceivedCharacter('a'),
+            },
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::CloseRequested,
+            },
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::ThemeChanged(crate::window::Theme::Dark),
+            },
+            Event::MainEventsCleared,
+        ]);
+        assert_eq!(flow, ControlFlow::Continue);
+        assert!(!app.is_exiting());
+        assert_eq!(app.event_handler().raw_events, 4);
+        assert_eq!(app.event_handler().characters, vec!['A']);
+
+        // Consumed events are still tracked, but they can't be the reason of an exit.
+        app.dispatch(key_event(window_id, ElementState::Pressed));
+        app.dispatch(key_event(window_id, ElementState::Released));
+        assert_eq!(app.event_handler().key_states, vec![true, false]);
+        assert_eq!(
+            app.dispatch(Event::WindowEvent {
+                window_id,
+                event: WindowEvent::Destroyed,
+            }),
+            ControlFlow::Exit
+        );
+        assert_eq!(
+            app.finish().unwrap().reason(),
+            ExitReason::EventHandlerRequest
+        );
     }
 }
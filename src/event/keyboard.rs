@@ -1,3 +1,67 @@
 extern crate winit;
 
+use std::collections::BTreeMap;
+
 pub use winit::event::{ModifiersState, ScanCode, VirtualKeyCode as KeyCode};
+
+use super::{DeviceId, ElementState};
+use crate::window::WindowId;
+
+// Assuming at most a certain number of scancodes. It should be enough.
+const MAX_SCAN_CODES: usize = 128;
+
+// Key states are tracked per window, or with no window for device events.
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardState {
+    state: BTreeMap<(Option<WindowId>, DeviceId), [ElementState; MAX_SCAN_CODES]>,
+}
+
+impl KeyboardState {
+    pub fn new() -> Self {
+        Self {
+            state: BTreeMap::new(),
+        }
+    }
+
+    pub fn key_state(
+        &self,
+        window_id: Option<WindowId>,
+        device_id: DeviceId,
+        scan_code: ScanCode,
+    ) -> ElementState {
+        self.state
+            .get(&(window_id, device_id))
+            .and_then(|keyboard_state| keyboard_state.get(scan_code as usize))
+            .copied()
+            .unwrap_or(ElementState::Released)
+    }
+
+    pub fn is_key_pressed(&self, window_id: Option<WindowId>, scan_code: ScanCode) -> bool {
+        self.state
+            .iter()
+            .filter(|((wid, _), _)| *wid == window_id)
+            .any(|(_, keyboard_state)| {
+                keyboard_state.get(scan_code as usize) == Some(&ElementState::Pressed)
+            })
+    }
+
+    pub(crate) fn key_state_mut(
+        &mut self,
+        window_id: Option<WindowId>,
+        device_id: DeviceId,
+        scan_code: ScanCode,
+    ) -> &mut ElementState {
+        let keyboard_state = self
+            .state
+            .entry((window_id, device_id))
+            .or_insert([ElementState::Released; MAX_SCAN_CODES]);
+        // Asserting just for safety.
+        let key_idx = scan_code as usize;
+        assert!(
+            key_idx < keyboard_state.len(),
+            "Invalid scan code {}",
+            key_idx
+        );
+        &mut keyboard_state[key_idx]
+    }
+}
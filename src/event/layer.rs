@@ -0,0 +1,54 @@
+use super::{ControlFlow, Event, EventContext, EventHandler};
+use crate::application::{FixedUpdateChannelId, JobHandle};
+
+// Events of the event loop are forwarded as they are, except for user events, which are
+// forwarded together with the custom events of timers and tasks.
+#[derive(Debug, PartialEq)]
+pub enum LayerCallback<'a, CustomEvent: 'static> {
+    Event(&'a Event<'a, CustomEvent>),
+    CustomEvent(&'a CustomEvent),
+    JobProgress {
+        job: JobHandle,
+        progress: f32,
+    },
+    JobCompleted {
+        job: JobHandle,
+        event: &'a CustomEvent,
+    },
+    JobFailed {
+        job: JobHandle,
+    },
+    FixedUpdate {
+        channel: FixedUpdateChannelId,
+        dt: std::time::Duration,
+    },
+    FixedUpdatesDropped {
+        channel: FixedUpdateChannelId,
+        dropped_time: std::time::Duration,
+    },
+    VariableUpdate {
+        dt: std::time::Duration,
+        fixed_update_alpha: f64,
+    },
+    MainEventsCleared,
+    FrameDeadlineMissed {
+        lateness: std::time::Duration,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LayerResponse {
+    Pass,
+    Consume(ControlFlow),
+}
+
+pub trait Layer<EventHandlerType>: Send
+where
+    EventHandlerType: EventHandler,
+{
+    fn on_callback(
+        &mut self,
+        context: &mut EventContext<EventHandlerType::CustomEvent>,
+        callback: &LayerCallback<EventHandlerType::CustomEvent>,
+    ) -> Result<LayerResponse, EventHandlerType::Error>;
+}
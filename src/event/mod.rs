@@ -9,5 +9,18 @@ pub use event::*;
 mod event_loop_any_thread;
 pub use event_loop_any_thread::*;
 
+mod event_context;
+pub use event_context::EventContext;
+pub(crate) use event_context::LoopRequests;
+
 mod event_handler;
 pub use event_handler::*;
+
+mod layer;
+pub use layer::*;
+
+mod scene;
+pub use scene::*;
+
+mod scene_stack;
+pub use scene_stack::*;
@@ -0,0 +1,146 @@
+use super::{Event, EventContext, EventLoop, RawEventResponse};
+use crate::{
+    application::{ApplicationHandle, FixedUpdateChannelId},
+    window::WindowId,
+};
+
+// Transitions apply to the scene requesting them: pushed scenes go right above it, and popped or
+// replaced scenes are the requesting one itself.
+pub enum SceneTransition<Error, CustomEvent>
+where
+    Error: std::fmt::Display + std::error::Error + 'static,
+    CustomEvent: 'static,
+{
+    None,
+    Push(Box<dyn Scene<Error = Error, CustomEvent = CustomEvent>>),
+    Pop,
+    Replace(Box<dyn Scene<Error = Error, CustomEvent = CustomEvent>>),
+}
+
+impl<Error, CustomEvent> std::fmt::Debug for SceneTransition<Error, CustomEvent>
+where
+    Error: std::fmt::Display + std::error::Error + 'static,
+    CustomEvent: 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneTransition::None => write!(f, "None"),
+            SceneTransition::Push(_) => write!(f, "Push"),
+            SceneTransition::Pop => write!(f, "Pop"),
+            SceneTransition::Replace(_) => write!(f, "Replace"),
+        }
+    }
+}
+
+// Each flag lets the corresponding callbacks reach the scene underneath as well.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ScenePropagation {
+    pub input: bool,
+    pub updates: bool,
+    pub rendering: bool,
+}
+
+pub trait Scene {
+    type Error: std::fmt::Display + std::error::Error + 'static;
+    type CustomEvent: 'static;
+
+    fn propagation(&self) -> ScenePropagation {
+        ScenePropagation::default()
+    }
+
+    fn on_enter(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn on_exit(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn on_pause(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn on_resume(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    // Runs on every event reaching the scene, before on_event. Consuming an event also hides it
+    // from the scenes underneath and from the handler of the stack.
+    fn on_raw_event<'a>(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        event: Event<'a, Self::CustomEvent>,
+    ) -> Result<RawEventResponse<'a, Self::CustomEvent>, Self::Error> {
+        Ok(RawEventResponse::Dispatch(event))
+    }
+
+    // Window and device events are forwarded before going through the typed dispatch. Only
+    // input events are limited by the input propagation.
+    fn on_event(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _event: &Event<Self::CustomEvent>,
+    ) -> Result<SceneTransition<Self::Error, Self::CustomEvent>, Self::Error> {
+        Ok(SceneTransition::None)
+    }
+
+    fn on_custom_event(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _event: &Self::CustomEvent,
+    ) -> Result<SceneTransition<Self::Error, Self::CustomEvent>, Self::Error> {
+        Ok(SceneTransition::None)
+    }
+
+    fn on_fixed_update(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _channel: FixedUpdateChannelId,
+        _dt: std::time::Duration,
+    ) -> Result<SceneTransition<Self::Error, Self::CustomEvent>, Self::Error> {
+        Ok(SceneTransition::None)
+    }
+
+    fn on_variable_update(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _dt: std::time::Duration,
+        _fixed_update_alpha: f64,
+    ) -> Result<SceneTransition<Self::Error, Self::CustomEvent>, Self::Error> {
+        Ok(SceneTransition::None)
+    }
+
+    fn on_main_events_cleared(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<SceneTransition<Self::Error, Self::CustomEvent>, Self::Error> {
+        Ok(SceneTransition::None)
+    }
+
+    fn on_redraw_requested(
+        &mut self,
+        _context: &mut EventContext<Self::CustomEvent>,
+        _wid: WindowId,
+    ) -> Result<SceneTransition<Self::Error, Self::CustomEvent>, Self::Error> {
+        Ok(SceneTransition::None)
+    }
+}
+
+pub trait InitialScene: Scene + Sized + 'static {
+    fn new(
+        event_loop: &EventLoop<Self::CustomEvent>,
+        application: &ApplicationHandle<Self::CustomEvent>,
+    ) -> Result<Self, Self::Error>;
+}
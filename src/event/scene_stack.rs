@@ -0,0 +1,658 @@
+use super::{
+    ControlFlow, Event, EventContext, EventHandler, EventLoop, InitialScene, RawEventResponse,
+    Scene, ScenePropagation, SceneTransition, WindowEvent,
+};
+use crate::{
+    application::{ApplicationHandle, ExitReason, FixedUpdateChannelId},
+    window::WindowId,
+};
+
+type BoxedScene<InitialSceneType> = Box<
+    dyn Scene<
+        Error = <InitialSceneType as Scene>::Error,
+        CustomEvent = <InitialSceneType as Scene>::CustomEvent,
+    >,
+>;
+
+pub struct SceneStack<InitialSceneType>
+where
+    InitialSceneType: InitialScene,
+{
+    scenes: Vec<BoxedScene<InitialSceneType>>,
+    entered: bool,
+}
+
+impl<InitialSceneType> SceneStack<InitialSceneType>
+where
+    InitialSceneType: InitialScene,
+{
+    pub fn from_initial_scene(scene: InitialSceneType) -> Self {
+        Self {
+            scenes: vec![Box::new(scene)],
+            entered: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    // The stack is created before any context is available, so the initial scene is entered on
+    // the first forwarded callback.
+    fn enter_initial_scene(
+        &mut self,
+        context: &mut EventContext<InitialSceneType::CustomEvent>,
+    ) -> Result<(), InitialSceneType::Error> {
+        if !self.entered {
+            self.entered = true;
+            if let Some(scene) = self.scenes.last_mut() {
+                scene.on_enter(context)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Input reaches the scenes from the top down, while updates and rendering reach them from the
+    // bottom up, so that overlays are drawn last. Transitions are applied once every scene has been
+    // reached, each relative to the scene requesting it, starting from the top of the stack.
+    fn forward<P, F>(
+        &mut self,
+        context: &mut EventContext<InitialSceneType::CustomEvent>,
+        propagates: P,
+        top_down: bool,
+        mut callback: F,
+    ) -> Result<ControlFlow, InitialSceneType::Error>
+    where
+        P: Fn(ScenePropagation) -> bool,
+        F: FnMut(
+            &mut BoxedScene<InitialSceneType>,
+            &mut EventContext<InitialSceneType::CustomEvent>,
+        ) -> Result<
+            SceneTransition<InitialSceneType::Error, InitialSceneType::CustomEvent>,
+            InitialSceneType::Error,
+        >,
+    {
+        self.enter_initial_scene(context)?;
+        let mut indices: Vec<usize> = (self.first_scene(propagates)..self.scenes.len()).collect();
+        if top_down {
+            indices.reverse();
+        }
+        let mut transitions = Vec::new();
+        for i in indices {
+            match callback(&mut self.scenes[i], context)? {
+                SceneTransition::None => (),
+                transition => transitions.push((i, transition)),
+            }
+        }
+        transitions.sort_by_key(|(i, _)| std::cmp::Reverse(*i));
+        for (i, transition) in transitions {
+            self.apply_transition(context, i, transition)?;
+        }
+        Ok(self.control_flow())
+    }
+
+    // The bottom-most scene reached when forwarding from the top of the stack.
+    fn first_scene<P>(&self, propagates: P) -> usize
+    where
+        P: Fn(ScenePropagation) -> bool,
+    {
+        let mut first = self.scenes.len();
+        while first > 0 {
+            first -= 1;
+            if !propagates(self.scenes[first].propagation()) {
+                break;
+            }
+        }
+        first
+    }
+
+    // Scenes covered by other scenes are paused, and the scene left on top is resumed.
+    fn apply_transition(
+        &mut self,
+        context: &mut EventContext<InitialSceneType::CustomEvent>,
+        index: usize,
+        transition: SceneTransition<InitialSceneType::Error, InitialSceneType::CustomEvent>,
+    ) -> Result<(), InitialSceneType::Error> {
+        match transition {
+            SceneTransition::None => (),
+            SceneTransition::Push(scene) => {
+                if index + 1 == self.scenes.len() {
+                    self.scenes[index].on_pause(context)?;
+                }
+                self.enter(context, index + 1, scene)?;
+            }
+            SceneTransition::Pop => {
+                self.scenes.remove(index).on_exit(context)?;
+                if index == self.scenes.len() {
+                    if let Some(top) = self.scenes.last_mut() {
+                        top.on_resume(context)?;
+                    }
+                }
+            }
+            SceneTransition::Replace(scene) => {
+                self.scenes.remove(index).on_exit(context)?;
+                self.enter(context, index, scene)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn enter(
+        &mut self,
+        context: &mut EventContext<InitialSceneType::CustomEvent>,
+        index: usize,
+        scene: BoxedScene<InitialSceneType>,
+    ) -> Result<(), InitialSceneType::Error> {
+        self.scenes.insert(index, scene);
+        self.scenes[index].on_enter(context)?;
+        if index + 1 < self.scenes.len() {
+            self.scenes[index].on_pause(context)?;
+        }
+        Ok(())
+    }
+
+    // Popping the last scene exits the application.
+    fn control_flow(&self) -> ControlFlow {
+        if self.scenes.is_empty() {
+            ControlFlow::Exit
+        } else {
+            ControlFlow::Continue
+        }
+    }
+}
+
+impl<InitialSceneType> EventHandler for SceneStack<InitialSceneType>
+where
+    InitialSceneType: InitialScene,
+{
+    type Error = InitialSceneType::Error;
+    type CustomEvent = InitialSceneType::CustomEvent;
+
+    fn new(
+        event_loop: &EventLoop<Self::CustomEvent>,
+        application: &ApplicationHandle<Self::CustomEvent>,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self::from_initial_scene(InitialSceneType::new(
+            event_loop,
+            application,
+        )?))
+    }
+
+    fn on_raw_event<'a>(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        event: Event<'a, Self::CustomEvent>,
+    ) -> Result<RawEventResponse<'a, Self::CustomEvent>, Self::Error> {
+        self.enter_initial_scene(context)?;
+        let is_input = is_input_event(&event);
+        let first = self.first_scene(|propagation| !is_input || propagation.input);
+        let mut event = event;
+        for i in (first..self.scenes.len()).rev() {
+            event = match self.scenes[i].on_raw_event(context, event)? {
+                RawEventResponse::Dispatch(event) => event,
+                response => return Ok(response),
+            };
+        }
+        if let Event::WindowEvent { .. } | Event::DeviceEvent { .. } = event {
+            let is_input = is_input_event(&event);
+            let control_flow = self.forward(
+                context,
+                |propagation| !is_input || propagation.input,
+                true,
+                |scene, context| scene.on_event(context, &event),
+            )?;
+            if control_flow == ControlFlow::Exit {
+                return Ok(RawEventResponse::Consume(ControlFlow::Exit));
+            }
+        }
+        Ok(RawEventResponse::Dispatch(event))
+    }
+
+    fn on_custom_event(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        event: Self::CustomEvent,
+    ) -> Result<ControlFlow, Self::Error> {
+        self.forward(
+            context,
+            |propagation| propagation.input,
+            true,
+            |scene, context| scene.on_custom_event(context, &event),
+        )
+    }
+
+    fn on_fixed_update(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        channel: FixedUpdateChannelId,
+        dt: std::time::Duration,
+    ) -> Result<ControlFlow, Self::Error> {
+        self.forward(
+            context,
+            |propagation| propagation.updates,
+            false,
+            |scene, context| scene.on_fixed_update(context, channel, dt),
+        )
+    }
+
+    fn on_variable_update(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        dt: std::time::Duration,
+        fixed_update_alpha: f64,
+    ) -> Result<ControlFlow, Self::Error> {
+        self.forward(
+            context,
+            |propagation| propagation.updates,
+            false,
+            |scene, context| scene.on_variable_update(context, dt, fixed_update_alpha),
+        )
+    }
+
+    fn on_main_events_cleared(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+    ) -> Result<ControlFlow, Self::Error> {
+        self.forward(
+            context,
+            |propagation| propagation.rendering,
+            false,
+            |scene, context| scene.on_main_events_cleared(context),
+        )
+    }
+
+    fn on_redraw_requested(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        wid: WindowId,
+    ) -> Result<ControlFlow, Self::Error> {
+        self.forward(
+            context,
+            |propagation| propagation.rendering,
+            false,
+            |scene, context| scene.on_redraw_requested(context, wid),
+        )
+    }
+
+    fn on_shutdown(
+        &mut self,
+        context: &mut EventContext<Self::CustomEvent>,
+        _reason: ExitReason,
+    ) -> Result<(), Self::Error> {
+        // Every scene is exited, even after an error.
+        let mut result = Ok(());
+        if self.entered {
+            while let Some(mut scene) = self.scenes.pop() {
+                let scene_result = scene.on_exit(context);
+                if result.is_ok() {
+                    result = scene_result;
+                }
+            }
+        }
+        result
+    }
+}
+
+// Window lifecycle events, like resizes and focus changes, concern every scene.
+fn is_input_event<CustomEvent>(event: &Event<CustomEvent>) -> bool {
+    match event {
+        Event::DeviceEvent { .. } => true,
+        Event::WindowEvent { event, .. } => matches!(
+            event,
+            WindowEvent::DroppedFile(_)
+                | WindowEvent::HoveredFile(_)
+                | WindowEvent::HoveredFileCancelled
+                | WindowEvent::ReceivedCharacter(_)
+                | WindowEvent::KeyboardInput { .. }
+                | WindowEvent::ModifiersChanged(_)
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::CursorEntered { .. }
+                | WindowEvent::CursorLeft { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::TouchpadPressure { .. }
+                | WindowEvent::AxisMotion { .. }
+                | WindowEvent::Touch(_)
+        ),
+        _ => false,
+    }
+}
+
+impl<InitialSceneType> std::fmt::Debug for SceneStack<InitialSceneType>
+where
+    InitialSceneType: InitialScene,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SceneStack")
+            .field("len", &self.scenes.len())
+            .field("entered", &self.entered)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{ApplicationBuilder, HeadlessApplication},
+        event::WindowEvent,
+    };
+
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    struct MyError;
+
+    impl std::fmt::Display for MyError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "MyError")
+        }
+    }
+
+    impl std::error::Error for MyError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            None
+        }
+    }
+
+    thread_local! {
+        static LOG: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    fn log(entry: String) {
+        LOG.with(|log| log.borrow_mut().push(entry));
+    }
+
+    fn take_log() -> Vec<String> {
+        LOG.with(|log| log.borrow_mut().drain(..).collect())
+    }
+
+    struct TestScene {
+        name: &'static str,
+        propagation: ScenePropagation,
+    }
+
+    impl Scene for TestScene {
+        type Error = MyError;
+        type CustomEvent = ();
+
+        fn propagation(&self) -> ScenePropagation {
+            self.propagation
+        }
+
+        fn on_enter(&mut self, _context: &mut EventContext<()>) -> Result<(), Self::Error> {
+            log(format!("{} enter", self.name));
+            Ok(())
+        }
+
+        fn on_exit(&mut self, _context: &mut EventContext<()>) -> Result<(), Self::Error> {
+            log(format!("{} exit", self.name));
+            match self.name {
+                "broken" => Err(MyError),
+                _ => Ok(()),
+            }
+        }
+
+        fn on_pause(&mut self, _context: &mut EventContext<()>) -> Result<(), Self::Error> {
+            log(format!("{} pause", self.name));
+            Ok(())
+        }
+
+        fn on_resume(&mut self, _context: &mut EventContext<()>) -> Result<(), Self::Error> {
+            log(format!("{} resume", self.name));
+            Ok(())
+        }
+
+        fn on_raw_event<'a>(
+            &mut self,
+            _context: &mut EventContext<()>,
+            event: Event<'a, ()>,
+        ) -> Result<RawEventResponse<'a, ()>, Self::Error> {
+            match event {
+                Event::WindowEvent {
+                    event: WindowEvent::ReceivedCharacter('x'),
+                    ..
+                } if self.name == "pause" => {
+                    log(format!("{} raw x", self.name));
+                    Ok(RawEventResponse::Consume(ControlFlow::Continue))
+                }
+                event => Ok(RawEventResponse::Dispatch(event)),
+            }
+        }
+
+        fn on_event(
+            &mut self,
+            _context: &mut EventContext<()>,
+            event: &Event<()>,
+        ) -> Result<SceneTransition<MyError, ()>, Self::Error> {
+            if let Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } = event
+            {
+                log(format!("{} focused {}", self.name, focused));
+            }
+            if let Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(c),
+                ..
+            } = event
+            {
+                log(format!("{} {}", self.name, c));
+                return Ok(match c {
+                    'p' => SceneTransition::Push(Box::new(TestScene {
+                        name: "pause",
+                        propagation: ScenePropagation {
+                            input: false,
+                            updates: false,
+                            rendering: true,
+                        },
+                    })),
+                    'r' => SceneTransition::Replace(Box::new(TestScene {
+                        name: "menu",
+                        propagation: ScenePropagation::default(),
+                    })),
+                    'h' => SceneTransition::Push(Box::new(TestScene {
+                        name: "hud",
+                        propagation: ScenePropagation {
+                            input: true,
+                            updates: true,
+                            rendering: true,
+                        },
+                    })),
+                    'g' if self.name == "gameplay" => {
+                        SceneTransition::Replace(Box::new(TestScene {
+                            name: "menu",
+                            propagation: ScenePropagation::default(),
+                        }))
+                    }
+                    'q' => SceneTransition::Pop,
+                    'b' => SceneTransition::Push(Box::new(TestScene {
+                        name: "broken",
+                        propagation: ScenePropagation::default(),
+                    })),
+                    _ => SceneTransition::None,
+                });
+            }
+            Ok(SceneTransition::None)
+        }
+
+        fn on_variable_update(
+            &mut self,
+            _context: &mut EventContext<()>,
+            _dt: std::time::Duration,
+            _fixed_update_alpha: f64,
+        ) -> Result<SceneTransition<MyError, ()>, Self::Error> {
+            log(format!("{} update", self.name));
+            Ok(SceneTransition::None)
+        }
+
+        fn on_main_events_cleared(
+            &mut self,
+            _context: &mut EventContext<()>,
+        ) -> Result<SceneTransition<MyError, ()>, Self::Error> {
+            log(format!("{} draw", self.name));
+            Ok(SceneTransition::None)
+        }
+    }
+
+    impl InitialScene for TestScene {
+        fn new(_: &EventLoop<()>, _: &ApplicationHandle<()>) -> Result<Self, Self::Error> {
+            Ok(Self {
+                name: "gameplay",
+                propagation: ScenePropagation::default(),
+            })
+        }
+    }
+
+    #[test]
+    fn transitions() {
+        let window_id = unsafe { WindowId::dummy() };
+        let character = |c| Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ReceivedCharacter(c),
+        };
+        let mut app = HeadlessApplication::new(
+            ApplicationBuilder::new()
+                .build::<SceneStack<TestScene>>()
+                .unwrap(),
+            SceneStack::from_initial_scene(TestScene {
+                name: "gameplay",
+                propagation: ScenePropagation::default(),
+            }),
+        );
+
+        app.dispatch_all(vec![character('p'), Event::MainEventsCleared]);
+        assert_eq!(app.event_handler().len(), 2);
+        assert_eq!(
+            take_log(),
+            vec![
+                "gameplay enter",
+                "gameplay p",
+                "gameplay pause",
+                "pause enter",
+                "pause update",
+                "gameplay draw",
+                "pause draw",
+            ]
+        );
+
+        // Lifecycle events reach every scene, while input stops at the pause scene.
+        app.dispatch_all(vec![
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::Focused(false),
+            },
+            character('x'),
+        ]);
+        assert_eq!(
+            take_log(),
+            vec![
+                "pause focused false",
+                "gameplay focused false",
+                "pause raw x"
+            ]
+        );
+
+        app.dispatch_all(vec![
+            character('a'),
+            character('q'),
+            Event::MainEventsCleared,
+        ]);
+        assert_eq!(
+            take_log(),
+            vec![
+                "pause a",
+                "pause q",
+                "pause exit",
+                "gameplay resume",
+                "gameplay update",
+                "gameplay draw",
+            ]
+        );
+
+        app.dispatch(character('r'));
+        assert_eq!(
+            take_log(),
+            vec!["gameplay r", "gameplay exit", "menu enter"]
+        );
+
+        assert_eq!(app.dispatch(character('q')), ControlFlow::Exit);
+        assert!(app.event_handler().is_empty());
+        assert_eq!(take_log(), vec!["menu q", "menu exit"]);
+        assert_eq!(
+            app.finish().unwrap().reason(),
+            ExitReason::EventHandlerRequest
+        );
+    }
+
+    #[test]
+    fn transitions_below_top() {
+        let window_id = unsafe { WindowId::dummy() };
+        let character = |c| Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ReceivedCharacter(c),
+        };
+        let mut app = HeadlessApplication::new(
+            ApplicationBuilder::new()
+                .build::<SceneStack<TestScene>>()
+                .unwrap(),
+            SceneStack::from_initial_scene(TestScene {
+                name: "gameplay",
+                propagation: ScenePropagation::default(),
+            }),
+        );
+        app.dispatch(character('h'));
+        take_log();
+
+        // The replaced scene keeps its place underneath the scene on top.
+        app.dispatch_all(vec![character('g'), Event::MainEventsCleared]);
+        assert_eq!(app.event_handler().len(), 2);
+        assert_eq!(
+            take_log(),
+            vec![
+                "hud g",
+                "gameplay g",
+                "gameplay exit",
+                "menu enter",
+                "menu pause",
+                "menu update",
+                "hud update",
+                "menu draw",
+                "hud draw",
+            ]
+        );
+
+        // Every transition requested in a pass is applied.
+        assert_eq!(app.dispatch(character('q')), ControlFlow::Exit);
+        assert!(app.event_handler().is_empty());
+        assert_eq!(
+            take_log(),
+            vec!["hud q", "menu q", "hud exit", "menu resume", "menu exit"]
+        );
+    }
+
+    #[test]
+    fn shutdown() {
+        let window_id = unsafe { WindowId::dummy() };
+        let mut app = HeadlessApplication::new(
+            ApplicationBuilder::new()
+                .build::<SceneStack<TestScene>>()
+                .unwrap(),
+            SceneStack::from_initial_scene(TestScene {
+                name: "gameplay",
+                propagation: ScenePropagation::default(),
+            }),
+        );
+        app.dispatch(Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ReceivedCharacter('b'),
+        });
+        take_log();
+
+        // A failing scene doesn't prevent the scenes underneath from exiting.
+        assert!(app.finish().is_err());
+        assert_eq!(take_log(), vec!["broken exit", "gameplay exit"]);
+    }
+}